@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use iced::Subscription;
+use iced_native::subscription;
+
+use crate::proc;
+use crate::Message;
+
+/// Everything the panes show, sampled at the same instant. What no open
+/// pane shows is left empty.
+#[derive(Debug)]
+pub struct Snapshot {
+    pub time: DateTime<Utc>,
+    pub cpu: Option<proc::CpuUsage>,
+    /// Empty when the cores report neither frequency nor throttling
+    pub cpu_freq: Vec<proc::CpuFreq>,
+    pub mem: Option<proc::MemInfo>,
+    pub load: Option<proc::SystemLoad>,
+    pub pressure: Option<proc::Pressure>,
+    pub sensors: Option<Vec<proc::Sensor>>,
+    /// Pressure of the cgroups selected in a pressure pane
+    pub cgroup_pressure: HashMap<String, io::Result<proc::Pressure>>,
+    pub disks: Option<Vec<proc::DiskUsage>>,
+    pub disk_io: Option<Vec<proc::DiskIo>>,
+    pub network: Option<Vec<proc::NetIo>>,
+    /// Finding the owners means going through the file descriptors of
+    /// every process
+    pub sockets: Option<Vec<proc::SocketInfo>>,
+    /// Empty when /proc could not be listed
+    pub processes: Vec<proc::ProcessInfo>,
+    /// Details of the processes open in a detail pane
+    pub process_details: HashMap<i32, io::Result<proc::ProcessDetail>>,
+    pub host: proc::HostInfo,
+}
+
+/// What the collector should sample and how often, shared with the UI so
+/// it can be changed without restarting the subscription. The flags tell
+/// whether a pane showing that kind of data is open; the samplers of the
+/// others are skipped.
+#[derive(Debug, Clone)]
+pub struct CollectorSettings {
+    pub interval: Duration,
+    pub detail_pids: HashSet<i32>,
+    pub cpu_freq: bool,
+    pub processes: bool,
+    pub sockets: bool,
+    pub disks: bool,
+    pub disk_io: bool,
+    pub network: bool,
+    pub pressure: bool,
+    pub sensors: bool,
+    pub host: bool,
+    /// Whether the disk usage includes pseudo filesystems
+    pub pseudo_filesystems: bool,
+    pub pressure_cgroups: HashSet<String>,
+    /// Where the cores are read from, normally `proc::CPU_ROOT`
+    pub cpu_root: PathBuf,
+    /// Where the hwmon chips are read from, normally `proc::HWMON_ROOT`
+    pub hwmon_root: PathBuf,
+}
+
+impl Default for CollectorSettings {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(1000),
+            detail_pids: HashSet::new(),
+            cpu_freq: false,
+            processes: false,
+            sockets: false,
+            disks: false,
+            disk_io: false,
+            network: false,
+            pressure: false,
+            sensors: false,
+            host: false,
+            pseudo_filesystems: false,
+            pressure_cgroups: HashSet::new(),
+            cpu_root: PathBuf::from(proc::CPU_ROOT),
+            hwmon_root: PathBuf::from(proc::HWMON_ROOT),
+        }
+    }
+}
+
+/// How long the collector sleeps at most before looking at the interval
+/// again, so that switching from a long interval to a short one does not
+/// wait for the long one to run out.
+const SETTINGS_POLL: Duration = Duration::from_millis(250);
+
+/// Keeps the state needed to compute usage from the difference between
+/// two samples.
+struct Collector {
+    settings: Arc<Mutex<CollectorSettings>>,
+    last_sample: Instant,
+    last_cpu: proc::CpuStat,
+    disk_usage: proc::DiskUsageSampler,
+    last_disk_stats: proc::DiskStatSample,
+    last_net_dev: proc::NetDevSample,
+    processes: proc::ProcessSampler,
+    /// Read once, then only refreshed in the parts that change
+    host: proc::HostInfo,
+}
+
+impl Collector {
+    fn new(settings: Arc<Mutex<CollectorSettings>>) -> Self {
+        Self {
+            settings,
+            last_sample: Instant::now(),
+            last_cpu: proc::CpuStat::default(),
+            disk_usage: proc::DiskUsageSampler::default(),
+            last_disk_stats: proc::DiskStatSample::default(),
+            last_net_dev: proc::NetDevSample::default(),
+            processes: proc::ProcessSampler::new(),
+            host: proc::HostInfo::read(),
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.settings
+            .lock()
+            .map(|settings| settings.interval)
+            .unwrap_or(Duration::from_millis(1000))
+    }
+
+    /// Waits until the interval has passed since the last sample.
+    async fn wait(&self) {
+        loop {
+            let remaining = self.interval().saturating_sub(self.last_sample.elapsed());
+            if remaining.is_zero() {
+                return;
+            }
+            tokio::time::sleep(remaining.min(SETTINGS_POLL)).await;
+        }
+    }
+
+    fn sample(&mut self) -> Snapshot {
+        self.last_sample = Instant::now();
+
+        let settings = self.settings.lock().map(|settings| settings.clone()).unwrap_or_default();
+
+        // The rates are computed over the time since the previous sample,
+        // so one taken before the pane was closed would spread whatever
+        // happened in between over the first sample after it is reopened
+        if !settings.disk_io {
+            self.last_disk_stats = proc::DiskStatSample::default();
+        }
+        if !settings.network {
+            self.last_net_dev = proc::NetDevSample::default();
+        }
+
+        if settings.host {
+            self.host.refresh();
+        }
+
+        Snapshot {
+            time: Utc::now(),
+            cpu: proc::get_cpuinfo(&mut self.last_cpu).ok(),
+            cpu_freq: if settings.cpu_freq {
+                proc::read_cpufreq(&settings.cpu_root)
+            } else {
+                Vec::new()
+            },
+            mem: proc::get_meminfo().ok(),
+            load: proc::read_system_load().ok(),
+            pressure: if settings.pressure { proc::read_pressure().ok() } else { None },
+            sensors: if settings.sensors {
+                proc::read_sensors(&settings.hwmon_root).ok()
+            } else {
+                None
+            },
+            cgroup_pressure: settings
+                .pressure_cgroups
+                .into_iter()
+                .map(|cgroup| {
+                    let pressure = proc::read_cgroup_pressure(&cgroup);
+                    (cgroup, pressure)
+                })
+                .collect(),
+            disks: if settings.disks {
+                self.disk_usage.sample(settings.pseudo_filesystems).ok()
+            } else {
+                None
+            },
+            disk_io: if settings.disk_io {
+                proc::get_diskio(&mut self.last_disk_stats).ok()
+            } else {
+                None
+            },
+            network: if settings.network {
+                proc::get_netio(&mut self.last_net_dev).ok()
+            } else {
+                None
+            },
+            sockets: if settings.sockets { proc::read_sockets().ok() } else { None },
+            processes: if settings.processes {
+                self.processes.sample().unwrap_or_default()
+            } else {
+                Vec::new()
+            },
+            process_details: settings
+                .detail_pids
+                .into_iter()
+                .map(|pid| (pid, proc::read_process_detail(pid, self.processes.clock_ticks())))
+                .collect(),
+            host: self.host.clone(),
+        }
+    }
+}
+
+enum State {
+    Starting(Arc<Mutex<CollectorSettings>>),
+    Running(Box<Collector>),
+}
+
+/// Samples the system at the interval set in `settings` on a blocking
+/// worker thread, away from the UI, and sends each snapshot as a
+/// `Message::Tick`.
+pub fn subscription(settings: Arc<Mutex<CollectorSettings>>) -> Subscription<Message> {
+    subscription::unfold(
+        std::any::TypeId::of::<Collector>(),
+        State::Starting(settings),
+        |state| async move {
+            let collector = match state {
+                State::Starting(settings) => Box::new(Collector::new(settings)),
+                State::Running(collector) => {
+                    collector.wait().await;
+                    collector
+                }
+            };
+
+            let (snapshot, collector) = tokio::task::spawn_blocking(move || {
+                let mut collector = collector;
+                (collector.sample(), collector)
+            })
+            .await
+            .expect("collector thread panicked");
+
+            (Some(Message::Tick(Arc::new(snapshot))), State::Running(collector))
+        },
+    )
+}
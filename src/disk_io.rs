@@ -0,0 +1,322 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use iced::alignment::Alignment;
+use iced::widget::{checkbox, column, pane_grid, row, text, Column, Row};
+use iced::{Element, Length};
+use plotters::prelude::ChartBuilder;
+use plotters_iced::plotters_backend::DrawingBackend;
+
+use crate::disk_usage::format_bytes;
+use crate::history::{self, HistoryChart, HistorySeries, TimeWindow};
+use crate::proc::DiskIo;
+use crate::{draw_line_series, draw_mesh, draw_series_labels, ChartSeries, Message};
+
+const CHART_HEIGHT: u16 = 200;
+
+/// Devices per row of the device selector.
+const SELECTOR_COLUMNS: usize = 6;
+
+/// What one of the charts of a device shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiskMetric {
+    Throughput,
+    Iops,
+    Latency,
+    QueueDepth,
+    Utilization,
+}
+
+impl DiskMetric {
+    const ALL: [DiskMetric; 5] = [
+        DiskMetric::Throughput,
+        DiskMetric::Iops,
+        DiskMetric::Latency,
+        DiskMetric::QueueDepth,
+        DiskMetric::Utilization,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            DiskMetric::Throughput => "Throughput",
+            DiskMetric::Iops => "IOPS",
+            DiskMetric::Latency => "Average wait per request",
+            DiskMetric::QueueDepth => "Average queue depth",
+            DiskMetric::Utilization => "Utilization",
+        }
+    }
+
+    fn series(&self) -> &'static [ChartSeries<DiskIo>] {
+        use plotters::style::RGBColor;
+
+        const READ: RGBColor = RGBColor(38, 110, 210);
+        const WRITE: RGBColor = RGBColor(220, 50, 47);
+
+        const THROUGHPUT: [ChartSeries<DiskIo>; 2] = [
+            ("read", READ, |d| d.read_bytes),
+            ("write", WRITE, |d| d.write_bytes),
+        ];
+        const IOPS: [ChartSeries<DiskIo>; 2] = [
+            ("read", READ, |d| d.read_iops),
+            ("write", WRITE, |d| d.write_iops),
+        ];
+        const LATENCY: [ChartSeries<DiskIo>; 2] = [
+            ("read", READ, |d| d.read_await),
+            ("write", WRITE, |d| d.write_await),
+        ];
+        const QUEUE_DEPTH: [ChartSeries<DiskIo>; 1] = [("in flight", RGBColor(237, 140, 30), |d| d.queue_depth)];
+        const UTILIZATION: [ChartSeries<DiskIo>; 1] = [("busy", RGBColor(181, 32, 186), |d| d.utilization)];
+
+        match self {
+            DiskMetric::Throughput => &THROUGHPUT,
+            DiskMetric::Iops => &IOPS,
+            DiskMetric::Latency => &LATENCY,
+            DiskMetric::QueueDepth => &QUEUE_DEPTH,
+            DiskMetric::Utilization => &UTILIZATION,
+        }
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self {
+            DiskMetric::Throughput => format!("{}/s", format_bytes(value as u64)),
+            DiskMetric::Iops => format!("{:.0}", value),
+            DiskMetric::Latency => format!("{:.1} ms", value),
+            DiskMetric::QueueDepth => format!("{:.1}", value),
+            DiskMetric::Utilization => format!("{:.0}%", value),
+        }
+    }
+
+    /// Smallest top of the y axis, so that an idle device does not get a
+    /// chart scaled to noise.
+    fn min_max(&self) -> f64 {
+        match self {
+            DiskMetric::Throughput => 1024.0 * 1024.0,
+            DiskMetric::Iops => 10.0,
+            DiskMetric::Latency => 1.0,
+            DiskMetric::QueueDepth => 1.0,
+            DiskMetric::Utilization => 100.0,
+        }
+    }
+}
+
+/// Activity history of one block device.
+struct DiskSeries {
+    name: String,
+    is_partition: bool,
+    is_virtual: bool,
+    /// One chart per `DiskMetric`
+    history: HistorySeries<DiskIo, { DiskMetric::ALL.len() }>,
+}
+
+impl DiskSeries {
+    fn new(disk: &DiskIo) -> Self {
+        Self {
+            name: disk.name.clone(),
+            is_partition: disk.is_partition,
+            is_virtual: disk.is_virtual,
+            history: HistorySeries::default(),
+        }
+    }
+}
+
+/// Throughput, IOPS, wait per request, queue depth and utilization of the
+/// block devices.
+#[derive(Default)]
+pub struct DiskIoChart {
+    devices: Vec<DiskSeries>,
+    newest_time: Option<DateTime<Utc>>,
+    time_window: TimeWindow,
+    /// Devices unchecked in the selector
+    hidden: HashSet<String>,
+    /// Whether partitions, loop devices and RAM disks are listed
+    show_all: bool,
+}
+
+impl DiskIoChart {
+    pub fn push_data(&mut self, time: DateTime<Utc>, disks: &[DiskIo]) {
+        for disk in disks {
+            let series = match self.devices.iter().position(|s| s.name == disk.name) {
+                Some(i) => &mut self.devices[i],
+                None => {
+                    self.devices.push(DiskSeries::new(disk));
+                    self.devices.last_mut().unwrap()
+                }
+            };
+            series.history.push(time, disk.clone());
+        }
+
+        for series in &mut self.devices {
+            series.history.trim(time);
+        }
+        // Forget devices that went away
+        self.devices.retain(|series| !series.history.is_empty());
+        self.newest_time = Some(time);
+    }
+
+    /// Forgets the devices and their history, keeping the selection.
+    pub fn clear_history(&mut self) {
+        self.devices.clear();
+        self.newest_time = None;
+    }
+
+    pub fn set_time_window(&mut self, time_window: TimeWindow) {
+        self.time_window = time_window;
+        for series in &self.devices {
+            series.history.clear_caches();
+        }
+    }
+
+    pub fn set_shown(&mut self, device: String, shown: bool) {
+        if shown {
+            self.hidden.remove(&device);
+        } else {
+            self.hidden.insert(device);
+        }
+    }
+
+    pub fn set_show_all(&mut self, show_all: bool) {
+        self.show_all = show_all;
+    }
+
+    fn is_listed(&self, series: &DiskSeries) -> bool {
+        self.show_all || !(series.is_partition || series.is_virtual)
+    }
+
+    fn view_selector(&self, pane: pane_grid::Pane) -> Column<'_, Message> {
+        let listed: Vec<&DiskSeries> = self.devices.iter().filter(|s| self.is_listed(s)).collect();
+
+        let mut col = column![checkbox(
+            "Show partitions and loop devices",
+            self.show_all,
+            move |show_all| Message::DiskIoShowAllToggled(pane, show_all),
+        )
+        .size(14)
+        .text_size(14)]
+        .spacing(5);
+
+        for chunk in listed.chunks(SELECTOR_COLUMNS) {
+            col = col.push(chunk.iter().fold(Row::new().spacing(10), |row, series| {
+                let name = series.name.clone();
+                row.push(
+                    checkbox(&series.name, !self.hidden.contains(&series.name), move |shown| {
+                        Message::DiskIoDeviceToggled(pane, name.clone(), shown)
+                    })
+                    .size(14)
+                    .text_size(14),
+                )
+            }));
+        }
+
+        col
+    }
+
+    fn view_device<'a>(
+        &self,
+        pane: pane_grid::Pane,
+        series: &'a DiskSeries,
+        newest_time: DateTime<Utc>,
+    ) -> Column<'a, Message> {
+        let range = self.time_window.range(newest_time);
+
+        let summary = match series.history.at(range.end) {
+            Some(disk) => format!(
+                "{}: read {}/s, write {}/s, {:.0} IOPS, {:.0}% busy",
+                series.name,
+                format_bytes(disk.read_bytes as u64),
+                format_bytes(disk.write_bytes as u64),
+                disk.read_iops + disk.write_iops,
+                disk.utilization,
+            ),
+            None => series.name.clone(),
+        };
+
+        let charts: Vec<Element<Message>> = DiskMetric::ALL
+            .iter()
+            .zip(series.history.caches().iter())
+            .map(|(metric, cache)| {
+                let chart = history::view_chart(
+                    DiskMetricChart {
+                        series,
+                        metric: *metric,
+                        range: range.clone(),
+                    },
+                    cache,
+                );
+
+                column![
+                    text(metric.title()).size(14),
+                    chart.map(move |scroll| Message::ChartScrolled(pane, scroll)),
+                ]
+                .width(Length::Fill)
+                .height(Length::Units(CHART_HEIGHT))
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect();
+
+        let mut col = column![text(summary).size(16)].spacing(10);
+        let mut charts = charts.into_iter();
+        while let Some(left) = charts.next() {
+            col = match charts.next() {
+                Some(right) => col.push(row![left, right].spacing(15)),
+                None => col.push(row![left]),
+            };
+        }
+
+        col
+    }
+
+    pub fn view(&self, pane: pane_grid::Pane) -> Element<'_, Message> {
+        let newest_time = match self.newest_time {
+            Some(time) if !self.devices.is_empty() => time,
+            _ => return text("Loading...").into(),
+        };
+
+        self.devices
+            .iter()
+            .filter(|series| self.is_listed(series) && !self.hidden.contains(&series.name))
+            .fold(
+                column![self.view_selector(pane)].spacing(20),
+                |col, series| col.push(self.view_device(pane, series, newest_time)),
+            )
+            .width(Length::Fill)
+            .into()
+    }
+}
+
+struct DiskMetricChart<'a> {
+    series: &'a DiskSeries,
+    metric: DiskMetric,
+    range: Range<DateTime<Utc>>,
+}
+
+impl HistoryChart for DiskMetricChart<'_> {
+    fn build_chart<DB: DrawingBackend>(&self, mut builder: ChartBuilder<DB>) {
+        let series = self.metric.series();
+        let max = self
+            .series
+            .history
+            .in_range(&self.range)
+            .flat_map(|disk| series.iter().map(move |(_, _, value)| value(disk)))
+            .fold(0.0, f64::max);
+        let max = match self.metric {
+            DiskMetric::Utilization => 100.0,
+            _ => (max * 1.1).max(self.metric.min_max()),
+        };
+
+        let mut chart = builder
+            .x_label_area_size(0)
+            .y_label_area_size(60)
+            .margin(10)
+            .build_cartesian_2d(self.range.clone(), 0.0..max)
+            .expect("failed to build chart");
+
+        let metric = self.metric;
+        draw_mesh(&mut chart, 5, &|y| metric.format(*y));
+
+        draw_line_series(&mut chart, self.series.history.iter(), &self.range, series);
+        draw_series_labels(&mut chart);
+    }
+}
@@ -0,0 +1,101 @@
+use iced::widget::{checkbox, column, pane_grid, progress_bar, text, Column};
+use iced::{Color, Element, Length};
+
+use crate::proc::DiskUsage;
+use crate::Message;
+
+/// Filesystems at least this full are highlighted.
+const FULL_PERCENT: f64 = 90.0;
+
+const FULL_COLOR: Color = Color::from_rgb(0.86, 0.2, 0.18);
+
+/// Formats a size in bytes with a binary unit.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "K", "M", "G", "T", "P"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+fn view_disk(disk: &DiskUsage) -> Column<'_, Message> {
+    let mount = &disk.mount;
+    let title = text(format!("{} ({}, {})", mount.mount_point, mount.fs_type, mount.source)).size(16);
+
+    let usage = match &disk.usage {
+        Ok(usage) => usage,
+        Err(e) => return column![title, text(format!("Unavailable: {}", e)).size(14)].spacing(5),
+    };
+
+    let used_percent = usage.used_percent();
+    let mut summary = text(format!(
+        "Size: {}; Used: {} ({:.0}%); Available: {}",
+        format_bytes(usage.total),
+        format_bytes(usage.used()),
+        used_percent,
+        format_bytes(usage.available),
+    ))
+    .size(14);
+    if used_percent >= FULL_PERCENT {
+        summary = summary.style(FULL_COLOR);
+    }
+
+    let mut col = column![
+        title,
+        summary,
+        progress_bar(0.0..=100.0, used_percent as f32).height(Length::Units(10)),
+    ]
+    .spacing(5);
+
+    // Some filesystems, like btrfs, have no fixed inode count
+    if usage.inodes > 0 {
+        let inodes_percent = usage.inodes_used() as f64 / usage.inodes as f64 * 100.0;
+        let mut inodes = text(format!(
+            "Inodes: {} of {} used ({:.0}%)",
+            usage.inodes_used(),
+            usage.inodes,
+            inodes_percent,
+        ))
+        .size(14);
+        if inodes_percent >= FULL_PERCENT {
+            inodes = inodes.style(FULL_COLOR);
+        }
+        col = col
+            .push(inodes)
+            .push(progress_bar(0.0..=100.0, inodes_percent as f32).height(Length::Units(10)));
+    }
+
+    col
+}
+
+pub fn view(
+    pane: pane_grid::Pane,
+    disks: Option<&Vec<DiskUsage>>,
+    show_pseudo: bool,
+) -> Element<'_, Message> {
+    let disks = match disks {
+        Some(disks) => disks,
+        None => return text("Loading...").into(),
+    };
+
+    let controls = checkbox("Show pseudo filesystems", show_pseudo, move |show| {
+        Message::DisksPseudoToggled(pane, show)
+    })
+    .size(14)
+    .text_size(14);
+
+    disks
+        .iter()
+        .filter(|disk| show_pseudo || !disk.mount.is_pseudo())
+        .fold(column![controls].spacing(15), |col, disk| col.push(view_disk(disk)))
+        .width(Length::Fill)
+        .into()
+}
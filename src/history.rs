@@ -0,0 +1,517 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use iced::event;
+use iced::keyboard;
+use iced::mouse;
+use iced::widget::canvas::{Cache, Cursor, Event, Frame, Geometry};
+use iced::{Element, Length, Rectangle, Size};
+use plotters::prelude::ChartBuilder;
+use plotters_iced::{plotters_backend::DrawingBackend, Chart, ChartWidget};
+
+/// How long the charts keep their samples, which is as far back as they
+/// can be scrolled.
+pub const HISTORY_LIMIT: Duration = Duration::from_secs(3600);
+
+/// Most points drawn per series. Longer windows are downsampled to this
+/// many buckets.
+pub const MAX_BUCKETS: usize = 300;
+
+/// Span of time shown by the charts of a pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryWindow {
+    #[default]
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+    OneHour,
+}
+
+impl HistoryWindow {
+    pub const ALL: [HistoryWindow; 4] = [
+        HistoryWindow::OneMinute,
+        HistoryWindow::FiveMinutes,
+        HistoryWindow::FifteenMinutes,
+        HistoryWindow::OneHour,
+    ];
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            HistoryWindow::OneMinute => Duration::from_secs(60),
+            HistoryWindow::FiveMinutes => Duration::from_secs(5 * 60),
+            HistoryWindow::FifteenMinutes => Duration::from_secs(15 * 60),
+            HistoryWindow::OneHour => Duration::from_secs(3600),
+        }
+    }
+}
+
+impl std::fmt::Display for HistoryWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryWindow::OneMinute => write!(f, "1 min"),
+            HistoryWindow::FiveMinutes => write!(f, "5 min"),
+            HistoryWindow::FifteenMinutes => write!(f, "15 min"),
+            HistoryWindow::OneHour => write!(f, "1 hour"),
+        }
+    }
+}
+
+/// A mouse wheel gesture over a chart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scroll {
+    /// Move back in time by this fraction of the window, or forward when
+    /// negative
+    Pan(f32),
+    /// Widen the window by this many steps, or narrow it when negative
+    Zoom(i32),
+    /// Go back to the newest samples
+    Live,
+}
+
+/// Window shown by a chart. It follows the newest sample until it is
+/// panned back, and then stays at the same time while samples come in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeWindow {
+    pub length: HistoryWindow,
+    /// End of the window, `None` to follow the newest sample
+    end: Option<DateTime<Utc>>,
+}
+
+impl TimeWindow {
+    pub fn range(&self, newest: DateTime<Utc>) -> Range<DateTime<Utc>> {
+        let length = chrono::Duration::from_std(self.length.duration()).unwrap_or_else(|_| chrono::Duration::zero());
+        let to = self.end.map_or(newest, |end| self.clamp(end, newest));
+        to - length..to
+    }
+
+    pub fn is_scrolled(&self) -> bool {
+        self.end.is_some()
+    }
+
+    pub fn set_length(&mut self, length: HistoryWindow) {
+        self.length = length;
+    }
+
+    /// Applies `scroll` to the window of a chart whose newest sample is
+    /// at `newest`.
+    pub fn apply(&mut self, scroll: Scroll, newest: DateTime<Utc>) {
+        match scroll {
+            Scroll::Pan(fraction) => {
+                let delta = chrono::Duration::from_std(self.length.duration().mul_f32(fraction.abs()))
+                    .unwrap_or_else(|_| chrono::Duration::zero());
+                let end = self.range(newest).end;
+                let end = if fraction > 0.0 { end - delta } else { end + delta };
+                // Panning up to the newest sample goes back to following it
+                self.end = (end < newest).then(|| self.clamp(end, newest));
+            }
+            Scroll::Zoom(steps) => {
+                let index = HistoryWindow::ALL
+                    .iter()
+                    .position(|w| *w == self.length)
+                    .unwrap_or(0) as i32;
+                let index = (index + steps).clamp(0, HistoryWindow::ALL.len() as i32 - 1);
+                self.length = HistoryWindow::ALL[index as usize];
+            }
+            Scroll::Live => self.end = None,
+        }
+    }
+
+    /// Keeps a window ending at `end` within the samples that are kept.
+    fn clamp(&self, end: DateTime<Utc>, newest: DateTime<Utc>) -> DateTime<Utc> {
+        let earliest = HISTORY_LIMIT.saturating_sub(self.length.duration());
+        let earliest = newest - chrono::Duration::from_std(earliest).unwrap_or_else(|_| chrono::Duration::zero());
+        end.clamp(earliest, newest)
+    }
+}
+
+/// Turns mouse wheel events over a chart into a `Scroll`: Shift + wheel
+/// pans and Ctrl + wheel zooms. The plain wheel is left to the enclosing
+/// `Scrollable`. `modifiers` is the chart state, which follows the
+/// keyboard so the wheel can be interpreted.
+pub fn scroll_event(
+    modifiers: &mut keyboard::Modifiers,
+    event: Event,
+    bounds: Rectangle,
+    cursor: Cursor,
+) -> (event::Status, Option<Scroll>) {
+    match event {
+        Event::Keyboard(keyboard::Event::ModifiersChanged(new)) => {
+            *modifiers = new;
+            (event::Status::Ignored, None)
+        }
+        Event::Mouse(mouse::Event::WheelScrolled { delta }) if cursor.is_over(&bounds) => {
+            // Some platforms turn Shift + wheel into horizontal scrolling
+            let (x, y) = match delta {
+                mouse::ScrollDelta::Lines { x, y } => (x, y),
+                mouse::ScrollDelta::Pixels { x, y } => (x / 40.0, y / 40.0),
+            };
+            let lines = if y == 0.0 { x } else { y };
+            if lines == 0.0 {
+                return (event::Status::Ignored, None);
+            }
+
+            let scroll = if modifiers.command() {
+                Scroll::Zoom(if lines > 0.0 { -1 } else { 1 })
+            } else if modifiers.shift() {
+                // Scrolling up goes back in time, a tenth of the window per line
+                Scroll::Pan(lines / 10.0)
+            } else {
+                return (event::Status::Ignored, None);
+            };
+            (event::Status::Captured, Some(scroll))
+        }
+        _ => (event::Status::Ignored, None),
+    }
+}
+
+/// Samples of one series over the last `HISTORY_LIMIT`, newest first, and
+/// the caches of the `N` charts drawn from them.
+pub struct HistorySeries<T, const N: usize> {
+    data_points: VecDeque<(DateTime<Utc>, T)>,
+    caches: [Cache; N],
+    limit: Duration,
+}
+
+impl<T, const N: usize> Default for HistorySeries<T, N> {
+    fn default() -> Self {
+        Self {
+            data_points: VecDeque::new(),
+            caches: [(); N].map(|_| Cache::new()),
+            limit: HISTORY_LIMIT,
+        }
+    }
+}
+
+impl<T, const N: usize> HistorySeries<T, N> {
+    pub fn push(&mut self, time: DateTime<Utc>, value: T) {
+        self.data_points.push_front((time, value));
+        self.trim(time);
+    }
+
+    /// Drops the samples that are too old at `now`. Every chart moves
+    /// along with the newest sample, so the caches are cleared too.
+    pub fn trim(&mut self, now: DateTime<Utc>) {
+        let limit = chrono::Duration::from_std(self.limit).unwrap_or_else(|_| chrono::Duration::zero());
+        while matches!(self.data_points.back(), Some((t, _)) if now - *t > limit) {
+            self.data_points.pop_back();
+        }
+        self.clear_caches();
+    }
+
+    pub fn clear(&mut self) {
+        self.data_points.clear();
+        self.clear_caches();
+    }
+
+    pub fn clear_caches(&self) {
+        for cache in &self.caches {
+            cache.clear();
+        }
+    }
+
+    pub fn caches(&self) -> &[Cache; N] {
+        &self.caches
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data_points.is_empty()
+    }
+
+    pub fn newest_time(&self) -> Option<DateTime<Utc>> {
+        self.data_points.front().map(|(time, _)| *time)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(DateTime<Utc>, T)> + Clone {
+        self.data_points.iter()
+    }
+
+    /// The newest sample taken no later than `time`, which is what a chart
+    /// scrolled back to `time` shows.
+    pub fn at(&self, time: DateTime<Utc>) -> Option<&T> {
+        self.data_points
+            .iter()
+            .find(|(t, _)| *t <= time)
+            .map(|(_, value)| value)
+    }
+
+    /// The samples drawn by a chart showing `range`.
+    pub fn in_range<'a>(&'a self, range: &'a Range<DateTime<Utc>>) -> impl Iterator<Item = &'a T> + Clone {
+        self.data_points
+            .iter()
+            .filter(|(time, _)| range.contains(time) || *time == range.end)
+            .map(|(_, value)| value)
+    }
+}
+
+/// A chart of a `HistorySeries`. `CachedChart` gives it a cache and the
+/// scrolling of `scroll_event`.
+pub trait HistoryChart {
+    fn build_chart<DB: DrawingBackend>(&self, builder: ChartBuilder<DB>);
+}
+
+struct CachedChart<'a, C> {
+    chart: C,
+    cache: &'a Cache,
+}
+
+impl<C: HistoryChart> Chart<Scroll> for CachedChart<'_, C> {
+    type State = keyboard::Modifiers;
+
+    #[inline]
+    fn draw<F: Fn(&mut Frame)>(&self, bounds: Size, draw_fn: F) -> Geometry {
+        self.cache.draw(bounds, draw_fn)
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Scroll>) {
+        scroll_event(state, event, bounds, cursor)
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, builder: ChartBuilder<DB>) {
+        self.chart.build_chart(builder);
+    }
+}
+
+/// Widget drawing `chart` into `cache`, filling the height it is given.
+pub fn view_chart<'a, C: HistoryChart + 'a>(chart: C, cache: &'a Cache) -> Element<'a, Scroll> {
+    ChartWidget::new(CachedChart { chart, cache })
+        .height(Length::Fill)
+        .into()
+}
+
+/// Min, max and average of the samples that fall in one slice of a window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    /// Time of the newest sample in the slice
+    pub time: DateTime<Utc>,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+}
+
+/// Reduces `value` of the samples in `range` to at most `MAX_BUCKETS`
+/// buckets of equal length. Samples are expected newest first, as the
+/// charts store them, and buckets come out in the same order. Every
+/// sample gets its own bucket when there are few enough of them, so
+/// calling this with the same samples and range always gives the same
+/// bucket times, whatever `value` is.
+pub fn downsample<'a, T: 'a>(
+    data_points: impl Iterator<Item = &'a (DateTime<Utc>, T)> + Clone,
+    range: &Range<DateTime<Utc>>,
+    value: impl Fn(&T) -> f64,
+) -> Vec<Bucket> {
+    let in_range = data_points.filter(|(time, _)| range.contains(time) || *time == range.end);
+
+    let span = (range.end - range.start).num_milliseconds().max(1);
+    let bucket_of = |time: &DateTime<Utc>| {
+        let age = (range.end - *time).num_milliseconds().clamp(0, span - 1);
+        (age * MAX_BUCKETS as i64 / span) as usize
+    };
+    let split = in_range.clone().count() > MAX_BUCKETS;
+
+    let mut buckets: Vec<Bucket> = Vec::new();
+    let mut current = None;
+    let mut count = 0;
+    for (time, point) in in_range {
+        let v = value(point);
+        let index = if split { bucket_of(time) } else { buckets.len() + 1 };
+        match buckets.last_mut() {
+            Some(bucket) if current == Some(index) => {
+                bucket.min = bucket.min.min(v);
+                bucket.max = bucket.max.max(v);
+                bucket.avg += (v - bucket.avg) / (count + 1) as f64;
+                count += 1;
+            }
+            _ => {
+                buckets.push(Bucket {
+                    time: *time,
+                    min: v,
+                    max: v,
+                    avg: v,
+                });
+                current = Some(index);
+                count = 1;
+            }
+        }
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    /// One sample per second from `from` to `to` included, newest first,
+    /// valued at their second.
+    fn samples(from: i64, to: i64) -> Vec<(DateTime<Utc>, f64)> {
+        (from..=to).rev().map(|s| (at(s), s as f64)).collect()
+    }
+
+    #[test]
+    fn downsamples() {
+        struct Case {
+            samples: Vec<(DateTime<Utc>, f64)>,
+            range: Range<DateTime<Utc>>,
+            buckets: usize,
+            /// Time, min, max and average of the newest bucket
+            newest: Option<(DateTime<Utc>, f64, f64, f64)>,
+        }
+
+        let cases = [
+            Case {
+                samples: Vec::new(),
+                range: at(0)..at(60),
+                buckets: 0,
+                newest: None,
+            },
+            // Few enough samples for one bucket each, the end of the range
+            // included
+            Case {
+                samples: samples(0, 60),
+                range: at(0)..at(60),
+                buckets: 61,
+                newest: Some((at(60), 60.0, 60.0, 60.0)),
+            },
+            // Samples outside the range are left out
+            Case {
+                samples: samples(0, 100),
+                range: at(30)..at(60),
+                buckets: 31,
+                newest: Some((at(60), 60.0, 60.0, 60.0)),
+            },
+            // Two samples per bucket
+            Case {
+                samples: samples(1, 600),
+                range: at(0)..at(600),
+                buckets: MAX_BUCKETS,
+                newest: Some((at(600), 599.0, 600.0, 599.5)),
+            },
+            Case {
+                samples: samples(1, 3600),
+                range: at(0)..at(3600),
+                buckets: MAX_BUCKETS,
+                newest: Some((at(3600), 3589.0, 3600.0, 3594.5)),
+            },
+        ];
+
+        for (i, case) in cases.iter().enumerate() {
+            let buckets = downsample(case.samples.iter(), &case.range, |v| *v);
+            assert_eq!(buckets.len(), case.buckets, "case {}", i);
+            let newest = buckets.first().map(|b| (b.time, b.min, b.max, b.avg));
+            assert_eq!(newest, case.newest, "case {}", i);
+            assert!(buckets.windows(2).all(|w| w[0].time > w[1].time), "case {}", i);
+        }
+    }
+
+    #[test]
+    fn downsamples_every_series_to_the_same_times() {
+        let samples = samples(1, 1000);
+        let range = at(0)..at(1000);
+        let times = |value: fn(&f64) -> f64| -> Vec<DateTime<Utc>> {
+            downsample(samples.iter(), &range, value).iter().map(|b| b.time).collect()
+        };
+        assert_eq!(times(|v| *v), times(|v| -v * 2.0));
+    }
+
+    #[test]
+    fn time_window_follows_the_newest_sample_until_panned() {
+        let mut window = TimeWindow::default();
+        assert_eq!(window.range(at(100)), at(40)..at(100));
+        assert_eq!(window.range(at(101)), at(41)..at(101));
+        assert!(!window.is_scrolled());
+
+        // Back by half a minute, then stays put while samples come in
+        window.apply(Scroll::Pan(0.5), at(101));
+        assert!(window.is_scrolled());
+        assert_eq!(window.range(at(101)), at(11)..at(71));
+        assert_eq!(window.range(at(200)), at(11)..at(71));
+
+        // Zooming keeps the end
+        window.apply(Scroll::Zoom(1), at(200));
+        assert_eq!(window.length, HistoryWindow::FiveMinutes);
+        assert_eq!(window.range(at(200)), at(-229)..at(71));
+
+        window.apply(Scroll::Live, at(200));
+        assert!(!window.is_scrolled());
+        assert_eq!(window.range(at(200)), at(-100)..at(200));
+    }
+
+    #[test]
+    fn time_window_goes_live_when_panned_past_the_newest_sample() {
+        let mut window = TimeWindow::default();
+        window.apply(Scroll::Pan(0.5), at(100));
+        window.apply(Scroll::Pan(-0.25), at(100));
+        assert_eq!(window.range(at(100)), at(25)..at(85));
+
+        window.apply(Scroll::Pan(-1.0), at(100));
+        assert!(!window.is_scrolled());
+        assert_eq!(window.range(at(130)), at(70)..at(130));
+    }
+
+    #[test]
+    fn time_window_stays_within_the_history() {
+        let mut window = TimeWindow::default();
+        window.apply(Scroll::Pan(1000.0), at(10_000));
+        let limit = HISTORY_LIMIT.as_secs() as i64;
+        assert_eq!(window.range(at(10_000)), at(10_000 - limit)..at(10_060 - limit));
+
+        // Samples falling off the history drag the window along
+        assert_eq!(window.range(at(10_100)), at(10_100 - limit)..at(10_160 - limit));
+    }
+
+    #[test]
+    fn only_modified_wheel_events_scroll() {
+        let bounds = Rectangle::new(iced::Point::ORIGIN, Size::new(100.0, 100.0));
+        let inside = Cursor::Available(iced::Point::new(50.0, 50.0));
+        let outside = Cursor::Available(iced::Point::new(150.0, 50.0));
+        let wheel = |y| Event::Mouse(mouse::Event::WheelScrolled {
+            delta: mouse::ScrollDelta::Lines { x: 0.0, y },
+        });
+
+        let cases = [
+            (keyboard::Modifiers::empty(), wheel(1.0), inside, None),
+            (keyboard::Modifiers::SHIFT, wheel(1.0), inside, Some(Scroll::Pan(0.1))),
+            (keyboard::Modifiers::SHIFT, wheel(-2.0), inside, Some(Scroll::Pan(-0.2))),
+            (keyboard::Modifiers::CTRL, wheel(1.0), inside, Some(Scroll::Zoom(-1))),
+            (keyboard::Modifiers::CTRL, wheel(-1.0), inside, Some(Scroll::Zoom(1))),
+            (keyboard::Modifiers::SHIFT, wheel(1.0), outside, None),
+            (keyboard::Modifiers::SHIFT, wheel(0.0), inside, None),
+            // Shift + wheel turned into horizontal scrolling
+            (
+                keyboard::Modifiers::SHIFT,
+                Event::Mouse(mouse::Event::WheelScrolled {
+                    delta: mouse::ScrollDelta::Lines { x: 1.0, y: 0.0 },
+                }),
+                inside,
+                Some(Scroll::Pan(0.1)),
+            ),
+        ];
+
+        for (i, (modifiers, event, cursor, expected)) in cases.into_iter().enumerate() {
+            let mut state = modifiers;
+            let (status, scroll) = scroll_event(&mut state, event, bounds, cursor);
+            assert_eq!(scroll, expected, "case {}", i);
+            let captured = if expected.is_some() { event::Status::Captured } else { event::Status::Ignored };
+            assert_eq!(status, captured, "case {}", i);
+        }
+
+        let mut state = keyboard::Modifiers::empty();
+        let changed = Event::Keyboard(keyboard::Event::ModifiersChanged(keyboard::Modifiers::SHIFT));
+        assert_eq!(scroll_event(&mut state, changed, bounds, inside), (event::Status::Ignored, None));
+        assert_eq!(state, keyboard::Modifiers::SHIFT);
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use iced::alignment::Alignment;
+use iced::widget::{column, pane_grid, text};
+use iced::{Element, Length};
+use plotters::prelude::ChartBuilder;
+use plotters_iced::plotters_backend::DrawingBackend;
+
+use crate::history::{self, HistoryChart, HistorySeries, TimeWindow};
+use crate::proc::SystemLoad;
+use crate::{draw_line_series, draw_mesh, draw_series_labels, ChartSeries, Message};
+
+const CHART_HEIGHT: u16 = 250;
+
+/// What one of the charts of the pane shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoadMetric {
+    LoadAverage,
+    RunQueue,
+}
+
+impl LoadMetric {
+    const ALL: [LoadMetric; 2] = [LoadMetric::LoadAverage, LoadMetric::RunQueue];
+
+    fn title(&self) -> &'static str {
+        match self {
+            LoadMetric::LoadAverage => "Load average",
+            LoadMetric::RunQueue => "Runnable and blocked tasks",
+        }
+    }
+
+    fn series(&self) -> &'static [ChartSeries<SystemLoad>] {
+        use plotters::style::RGBColor;
+
+        const LOAD_AVERAGE: [ChartSeries<SystemLoad>; 3] = [
+            ("1 min", RGBColor(220, 50, 47), |l| l.load1),
+            ("5 min", RGBColor(237, 140, 30), |l| l.load5),
+            ("15 min", RGBColor(38, 110, 210), |l| l.load15),
+        ];
+        const RUN_QUEUE: [ChartSeries<SystemLoad>; 2] = [
+            ("running", RGBColor(46, 160, 67), |l| l.procs_running as f64),
+            ("blocked", RGBColor(181, 32, 186), |l| l.procs_blocked as f64),
+        ];
+
+        match self {
+            LoadMetric::LoadAverage => &LOAD_AVERAGE,
+            LoadMetric::RunQueue => &RUN_QUEUE,
+        }
+    }
+}
+
+/// Load averages and run queue over time.
+#[derive(Default)]
+pub struct LoadChart {
+    /// One chart per `LoadMetric`
+    history: HistorySeries<SystemLoad, 2>,
+    time_window: TimeWindow,
+}
+
+impl LoadChart {
+    pub fn push_data(&mut self, time: DateTime<Utc>, load: SystemLoad) {
+        self.history.push(time, load);
+    }
+
+    pub fn set_time_window(&mut self, time_window: TimeWindow) {
+        self.time_window = time_window;
+        self.history.clear_caches();
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn view(&self, pane: pane_grid::Pane) -> Element<'_, Message> {
+        let newest_time = match self.history.newest_time() {
+            Some(time) => time,
+            None => return text("Loading...").into(),
+        };
+        let range = self.time_window.range(newest_time);
+
+        let summary = match self.history.at(range.end) {
+            Some(load) => format!(
+                "Load {:.2} {:.2} {:.2} on {} CPUs, {} running, {} blocked",
+                load.load1, load.load5, load.load15, load.cpus, load.procs_running, load.procs_blocked,
+            ),
+            None => String::new(),
+        };
+
+        LoadMetric::ALL
+            .iter()
+            .zip(self.history.caches().iter())
+            .fold(column![text(summary).size(16)].spacing(10), |col, (metric, cache)| {
+                let chart = history::view_chart(
+                    LoadMetricChart {
+                        history: &self.history,
+                        metric: *metric,
+                        range: range.clone(),
+                    },
+                    cache,
+                );
+
+                col.push(
+                    column![
+                        text(metric.title()).size(14),
+                        chart.map(move |scroll| Message::ChartScrolled(pane, scroll)),
+                    ]
+                    .width(Length::Fill)
+                    .height(Length::Units(CHART_HEIGHT))
+                    .spacing(5)
+                    .align_items(Alignment::Center),
+                )
+            })
+            .width(Length::Fill)
+            .into()
+    }
+}
+
+struct LoadMetricChart<'a> {
+    history: &'a HistorySeries<SystemLoad, 2>,
+    metric: LoadMetric,
+    range: Range<DateTime<Utc>>,
+}
+
+impl HistoryChart for LoadMetricChart<'_> {
+    fn build_chart<DB: DrawingBackend>(&self, mut builder: ChartBuilder<DB>) {
+        use plotters::{prelude::*, style::Color};
+
+        let series = self.metric.series();
+        let shown = self.history.in_range(&self.range);
+        let max = shown
+            .clone()
+            .flat_map(|load| series.iter().map(move |(_, _, value)| value(load)))
+            .fold(0.0, f64::max);
+        // The load is only meaningful next to the CPU count, so the
+        // reference line is always in view
+        let cpus = shown.map(|load| load.cpus).max().unwrap_or(0) as f64;
+        let max = match self.metric {
+            LoadMetric::LoadAverage => (max.max(cpus) * 1.1).max(1.0),
+            LoadMetric::RunQueue => (max * 1.1).max(1.0),
+        };
+
+        let mut chart = builder
+            .x_label_area_size(0)
+            .y_label_area_size(40)
+            .margin(10)
+            .build_cartesian_2d(self.range.clone(), 0.0..max)
+            .expect("failed to build chart");
+
+        draw_mesh(&mut chart, 5, &|y| format!("{:.1}", y));
+
+        if self.metric == LoadMetric::LoadAverage && cpus > 0.0 {
+            let style = ShapeStyle::from(BLACK.mix(0.5)).stroke_width(1);
+            chart
+                .draw_series(LineSeries::new([(self.range.start, cpus), (self.range.end, cpus)], style))
+                .expect("failed to draw chart data")
+                .label(format!("{} CPUs", cpus))
+                .legend(move |(x, y)| PathElement::new([(x, y), (x + 8, y)], style));
+        }
+
+        draw_line_series(&mut chart, self.history.iter(), &self.range, series);
+        draw_series_labels(&mut chart);
+    }
+}
@@ -1,33 +1,70 @@
 use std::collections::VecDeque;
-use std::time::{Duration, Instant};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use iced::alignment::{Alignment, Horizontal, Vertical};
-use iced::widget::canvas::{Cache, Frame, Geometry};
+use iced::widget::canvas::{self, Cache, Frame, Geometry};
 use iced::widget::progress_bar;
-use iced::{executor, time, Size};
+use iced::{executor, Size};
 use iced::keyboard;
 use iced::theme::{self, Theme};
 use iced::widget::pane_grid::{self, PaneGrid};
 use iced::widget::{button, column, container, row, scrollable, text, pick_list, Container, Column, Text, Row, Space, Scrollable};
 use iced::{
-    Application, Color, Command, Element, Length, Settings, Subscription,
+    Application, Color, Command, Element, Length, Rectangle, Settings, Subscription,
 };
 use iced_lazy::responsive;
 use iced_native::{event, subscription, Event};
+mod collector;
+mod disk_io;
+mod disk_usage;
+mod history;
+mod load;
+mod network;
+mod pressure;
 mod proc;
+mod process_detail;
+mod process_table;
+mod sensors;
+mod sockets;
+mod system_info;
+
+use history::{HistoryWindow, TimeWindow};
+use process_table::{ProcessAction, ProcessColumn, ProcessTable};
 
 pub fn main() -> iced::Result {
-    Grid::run(Settings::default())
+    let mut collector_settings = collector::CollectorSettings::default();
+    // Let the sensors pane and the CPU frequencies be tried against a fake
+    // sysfs tree
+    if let Some(root) = std::env::var_os("DASHBOARD_HWMON_ROOT") {
+        collector_settings.hwmon_root = root.into();
+    }
+    if let Some(root) = std::env::var_os("DASHBOARD_CPU_ROOT") {
+        collector_settings.cpu_root = root.into();
+    }
+
+    Grid::run(Settings::with_flags(collector_settings))
 }
 
 pub struct Grid {
     panes: pane_grid::State<Pane>,
     panes_created: usize,
     focus: Option<pane_grid::Pane>,
+    /// Latest sample from the collector, `None` until the first one
+    snapshot: Option<Arc<collector::Snapshot>>,
+    /// CPU usage history, shared by every CPU pane
+    cpu_history: CPUHistory,
+    collector_settings: Arc<Mutex<collector::CollectorSettings>>,
+    /// Refresh interval of the panes that do not override it
+    refresh_interval: RefreshInterval,
+    /// Snapshots are dropped while paused, so every pane keeps showing
+    /// what it showed when the pause started
+    paused: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
     Split(pane_grid::Axis, pane_grid::Pane),
     SplitFocused(pane_grid::Axis),
@@ -41,27 +78,157 @@ pub enum Message {
     Close(pane_grid::Pane),
     CloseFocused,
     InfoSelected(SystemInfo),
-    Tick,
+    CPUViewSelected(pane_grid::Pane, CPUView),
+    HistoryWindowSelected(pane_grid::Pane, HistoryWindow),
+    ChartScrolled(pane_grid::Pane, history::Scroll),
+    ProcessSortSelected(pane_grid::Pane, ProcessColumn),
+    ProcessFilterChanged(pane_grid::Pane, String),
+    ProcessFilterRegexToggled(pane_grid::Pane, bool),
+    ProcessTreeModeToggled(pane_grid::Pane, bool),
+    ProcessSubtreeToggled(pane_grid::Pane, i32),
+    ProcessSelected(pane_grid::Pane, i32),
+    ProcessActionRequested(pane_grid::Pane, ProcessAction),
+    ProcessActionConfirmed(pane_grid::Pane),
+    ProcessActionCancelled(pane_grid::Pane),
+    DisksPseudoToggled(pane_grid::Pane, bool),
+    DiskIoDeviceToggled(pane_grid::Pane, String, bool),
+    DiskIoShowAllToggled(pane_grid::Pane, bool),
+    NetworkInterfaceToggled(pane_grid::Pane, String, bool),
+    SocketsListeningToggled(pane_grid::Pane, bool),
+    SocketsPortChanged(pane_grid::Pane, String),
+    PressureCgroupChanged(pane_grid::Pane, String),
+    RefreshIntervalSelected(RefreshInterval),
+    PaneRefreshSelected(pane_grid::Pane, PaneRefresh),
+    TogglePause,
+    CopyToClipboard(String),
+    Tick(Arc<collector::Snapshot>),
     OpenTerminal,
 }
 
+impl Grid {
+    /// Shows `pid` in the detail pane opened from the process list in
+    /// `pane`, splitting a new one off the list if there is none yet.
+    fn show_process_detail(&mut self, pane: pane_grid::Pane, pid: i32) {
+        let detail_pane = self
+            .panes
+            .get(&pane)
+            .and_then(|p| p.detail_pane)
+            .filter(|detail_pane| {
+                matches!(
+                    self.panes.get(detail_pane).map(|p| p.selected_info),
+                    Some(SystemInfo::ProcessDetail(_))
+                )
+            });
+
+        let detail_pane = match detail_pane {
+            Some(detail_pane) => detail_pane,
+            None => {
+                let mut detail = Pane::new();
+                detail.selected_info = SystemInfo::ProcessDetail(pid);
+                match self.panes.split(pane_grid::Axis::Vertical, &pane, detail) {
+                    Some((detail_pane, _)) => {
+                        self.panes_created += 1;
+                        detail_pane
+                    }
+                    None => return,
+                }
+            }
+        };
+
+        if let Some(p) = self.panes.get_mut(&pane) {
+            p.detail_pane = Some(detail_pane);
+        }
+        if let Some(p) = self.panes.get_mut(&detail_pane) {
+            p.selected_info = SystemInfo::ProcessDetail(pid);
+        }
+    }
+
+    /// Tells the collector which processes are open in a detail pane,
+    /// which kinds of data the open panes show, and to sample as often as
+    /// the fastest pane needs.
+    fn sync_collector_settings(&self) {
+        let interval = self
+            .panes
+            .iter()
+            .map(|(_, pane)| pane.refresh.interval(self.refresh_interval))
+            .min()
+            .unwrap_or(self.refresh_interval.0);
+
+        let detail_pids = self
+            .panes
+            .iter()
+            .filter_map(|(_, pane)| match pane.selected_info {
+                SystemInfo::ProcessDetail(pid) => Some(pid),
+                _ => None,
+            })
+            .collect();
+
+        let shown = |info: SystemInfo| self.panes.iter().any(|(_, pane)| pane.selected_info == info);
+
+        let pseudo_filesystems = self
+            .panes
+            .iter()
+            .any(|(_, pane)| pane.selected_info == SystemInfo::Disks && pane.show_pseudo_fs);
+
+        let pressure_cgroups = self
+            .panes
+            .iter()
+            .filter(|(_, pane)| pane.selected_info == SystemInfo::Pressure)
+            .filter_map(|(_, pane)| pane.pressure.cgroup().map(String::from))
+            .collect();
+
+        if let Ok(mut settings) = self.collector_settings.lock() {
+            settings.interval = interval;
+            settings.detail_pids = detail_pids;
+            settings.cpu_freq = shown(SystemInfo::CPU);
+            settings.processes = shown(SystemInfo::Processes);
+            settings.sockets = shown(SystemInfo::Sockets);
+            settings.disks = shown(SystemInfo::Disks);
+            settings.disk_io = shown(SystemInfo::DiskIo);
+            settings.network = shown(SystemInfo::Network);
+            settings.pressure = shown(SystemInfo::Pressure);
+            settings.sensors = shown(SystemInfo::Sensors);
+            settings.host = shown(SystemInfo::Host);
+            settings.pseudo_filesystems = pseudo_filesystems;
+            settings.pressure_cgroups = pressure_cgroups;
+        }
+    }
+
+    /// Gives panes created since the last update the latest snapshot and
+    /// chart caches for the current CPU history.
+    fn sync_panes(&mut self) {
+        for (_, pane) in self.panes.iter_mut() {
+            if pane.snapshot.is_none() {
+                pane.snapshot = self.snapshot.clone();
+            }
+            pane.cpu_chart.sync_caches(&self.cpu_history);
+        }
+    }
+}
+
 impl Application for Grid {
     type Message = Message;
     type Theme = Theme;
     type Executor = executor::Default;
-    type Flags = ();
+    type Flags = collector::CollectorSettings;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
+    fn new(collector_settings: collector::CollectorSettings) -> (Self, Command<Message>) {
         let (panes, _) = pane_grid::State::new(Pane::new());
 
-        (
-            Grid {
-                panes,
-                panes_created: 1,
-                focus: None,
-            },
-            Command::none(),
-        )
+        let grid = Grid {
+            panes,
+            panes_created: 1,
+            focus: None,
+            snapshot: None,
+            cpu_history: CPUHistory::default(),
+            collector_settings: Arc::new(Mutex::new(collector_settings)),
+            refresh_interval: RefreshInterval::default(),
+            paused: false,
+        };
+        // So that the first sample already has what the first pane shows
+        grid.sync_collector_settings();
+
+        (grid, Command::none())
     }
 
     fn title(&self) -> String {
@@ -69,6 +236,8 @@ impl Application for Grid {
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
+        let mut command = Command::none();
+
         match message {
             Message::Split(axis, pane) => {
                 let result = self.panes.split(
@@ -150,22 +319,156 @@ impl Application for Grid {
             }
             Message::InfoSelected(info) => {
                 if let Some(pane) = self.focus {
-                    if let Some(Pane {selected_info, ..}) = self.panes.get_mut(&pane) {
-                        *selected_info = info;
+                    if let Some(pane) = self.panes.get_mut(&pane) {
+                        pane.select_info(info, &self.cpu_history);
                     }
                 }
             }
-            Message::Tick => {
-                for pane in self.panes.iter_mut() {
-                    pane.1.cpu_chart.update();
+            Message::CPUViewSelected(pane, view) => {
+                if let Some(Pane { cpu_chart, .. }) = self.panes.get_mut(&pane) {
+                    cpu_chart.view = view;
+                }
+            }
+            Message::HistoryWindowSelected(pane, length) => {
+                if let Some(pane) = self.panes.get_mut(&pane) {
+                    let mut time_window = pane.time_window;
+                    time_window.set_length(length);
+                    pane.set_time_window(time_window);
+                }
+            }
+            Message::ChartScrolled(pane, scroll) => {
+                if let Some(pane) = self.panes.get_mut(&pane) {
+                    // Scrolled relative to what the pane shows, which may
+                    // lag behind the latest snapshot
+                    let newest = pane.snapshot.as_ref().map_or_else(Utc::now, |snapshot| snapshot.time);
+                    let mut time_window = pane.time_window;
+                    time_window.apply(scroll, newest);
+                    pane.set_time_window(time_window);
+                }
+            }
+            Message::ProcessSortSelected(pane, column) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.sort_by(column);
+                }
+            }
+            Message::ProcessFilterChanged(pane, text) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.set_filter(text);
+                }
+            }
+            Message::ProcessFilterRegexToggled(pane, use_regex) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.set_filter_regex(use_regex);
+                }
+            }
+            Message::ProcessTreeModeToggled(pane, tree_mode) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.set_tree_mode(tree_mode);
+                }
+            }
+            Message::ProcessSubtreeToggled(pane, pid) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.toggle_subtree(pid);
+                }
+            }
+            Message::ProcessSelected(pane, pid) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.select(pid);
+                }
+                self.show_process_detail(pane, pid);
+            }
+            Message::ProcessActionRequested(pane, action) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.request_action(action);
+                }
+            }
+            Message::ProcessActionConfirmed(pane) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.confirm_action();
+                }
+            }
+            Message::ProcessActionCancelled(pane) => {
+                if let Some(Pane { process_table, .. }) = self.panes.get_mut(&pane) {
+                    process_table.cancel_action();
+                }
+            }
+            Message::DisksPseudoToggled(pane, show) => {
+                if let Some(Pane { show_pseudo_fs, .. }) = self.panes.get_mut(&pane) {
+                    *show_pseudo_fs = show;
+                }
+            }
+            Message::DiskIoDeviceToggled(pane, device, shown) => {
+                if let Some(Pane { disk_io, .. }) = self.panes.get_mut(&pane) {
+                    disk_io.set_shown(device, shown);
+                }
+            }
+            Message::DiskIoShowAllToggled(pane, show_all) => {
+                if let Some(Pane { disk_io, .. }) = self.panes.get_mut(&pane) {
+                    disk_io.set_show_all(show_all);
                 }
             }
+            Message::NetworkInterfaceToggled(pane, interface, shown) => {
+                if let Some(Pane { network, .. }) = self.panes.get_mut(&pane) {
+                    network.set_shown(interface, shown);
+                }
+            }
+            Message::SocketsListeningToggled(pane, listening_only) => {
+                if let Some(Pane { socket_filter, .. }) = self.panes.get_mut(&pane) {
+                    socket_filter.listening_only = listening_only;
+                }
+            }
+            Message::SocketsPortChanged(pane, port) => {
+                if let Some(Pane { socket_filter, .. }) = self.panes.get_mut(&pane) {
+                    socket_filter.port = port;
+                }
+            }
+            Message::PressureCgroupChanged(pane, cgroup) => {
+                if let Some(pane) = self.panes.get_mut(&pane) {
+                    pane.pressure.set_cgroup(cgroup);
+                }
+            }
+            Message::RefreshIntervalSelected(interval) => {
+                self.refresh_interval = interval;
+            }
+            Message::PaneRefreshSelected(pane, refresh) => {
+                if let Some(pane) = self.panes.get_mut(&pane) {
+                    pane.refresh = refresh;
+                }
+            }
+            Message::TogglePause => {
+                self.paused = !self.paused;
+            }
+            Message::Tick(snapshot) => {
+                if !self.paused {
+                    if let Some(cpu) = &snapshot.cpu {
+                        self.cpu_history.push_sample(snapshot.time, cpu, &snapshot.cpu_freq);
+                    }
+
+                    let sample_interval = self
+                        .collector_settings
+                        .lock()
+                        .map(|settings| settings.interval)
+                        .unwrap_or(self.refresh_interval.0);
+                    for (_, pane) in self.panes.iter_mut() {
+                        if pane.is_due(snapshot.time, self.refresh_interval, sample_interval) {
+                            pane.refresh(&snapshot, &self.cpu_history);
+                        }
+                    }
+                    self.snapshot = Some(snapshot);
+                }
+            }
+            Message::CopyToClipboard(contents) => {
+                command = iced::clipboard::write(contents);
+            }
             Message::OpenTerminal => {
                 proc::open_terminal();
             }
         }
 
-        Command::none()
+        self.sync_panes();
+        self.sync_collector_settings();
+
+        command
     }
 
     fn subscription(&self) -> Subscription<Message> {
@@ -185,14 +488,16 @@ impl Application for Grid {
                     },
                 }
             }),
-            time::every(Duration::from_millis(1000)).map(|_| Message::Tick),
+            collector::subscription(self.collector_settings.clone()),
         ])
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let focus = self.focus;
         let total_panes = self.panes.len();
 
+        let cpu_history = &self.cpu_history;
+
         let pane_grid = PaneGrid::new(&self.panes, |id, pane, is_maximized| {
             let is_focused = focus == Some(id);
 
@@ -213,13 +518,7 @@ impl Application for Grid {
             .spacing(5);
 
             let title_bar = pane_grid::TitleBar::new(title)
-                .controls(view_controls(
-                    id,
-                    total_panes,
-                    pane.is_pinned,
-                    is_maximized,
-                    pane.selected_info
-                ))
+                .controls(view_controls(id, pane, total_panes, is_maximized))
                 .padding(10)
                 .style(if is_focused {
                     style::title_bar_focused
@@ -228,7 +527,7 @@ impl Application for Grid {
                 });
 
             pane_grid::Content::new(responsive(move |_| {
-                view_content(&pane.cpu_chart, pane.selected_info)
+                view_content(id, pane, cpu_history)
             }))
             .title_bar(title_bar)
             .style(if is_focused {
@@ -244,7 +543,23 @@ impl Application for Grid {
         .on_drag(Message::Dragged)
         .on_resize(10, Message::Resized);
 
-        container(pane_grid)
+        let toolbar = row![
+            text("Refresh every").size(14),
+            pick_list(
+                &RefreshInterval::ALL[..],
+                Some(self.refresh_interval),
+                Message::RefreshIntervalSelected,
+            )
+            .text_size(14),
+            button(text(if self.paused { "Resume" } else { "Pause" }).size(14))
+                .padding(3)
+                .on_press(Message::TogglePause),
+            text(if self.paused { "Paused (Ctrl+P to resume)" } else { "" }).size(14),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        container(column![toolbar, pane_grid].spacing(10))
             .width(Length::Fill)
             .height(Length::Fill)
             .padding(10)
@@ -279,45 +594,133 @@ fn handle_hotkey(key_code: keyboard::KeyCode) -> Option<Message> {
         KeyCode::V => Some(Message::SplitFocused(Axis::Vertical)),
         KeyCode::H => Some(Message::SplitFocused(Axis::Horizontal)),
         KeyCode::W => Some(Message::CloseFocused),
+        KeyCode::P => Some(Message::TogglePause),
         _ => direction.map(Message::FocusAdjacent),
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SystemInfo {
     CPU,
     Mem,
+    #[default]
     Processes,
-    Uname,
+    Disks,
+    DiskIo,
+    Network,
+    Sockets,
+    Load,
+    Pressure,
+    Sensors,
+    Host,
+    /// Details of a single process, opened from the process table
+    ProcessDetail(i32),
 }
 
 impl SystemInfo {
-    const ALL: [SystemInfo; 4] = [
+    const ALL: [SystemInfo; 11] = [
         SystemInfo::CPU,
         SystemInfo::Mem,
         SystemInfo::Processes,
-        SystemInfo::Uname,
+        SystemInfo::Disks,
+        SystemInfo::DiskIo,
+        SystemInfo::Network,
+        SystemInfo::Sockets,
+        SystemInfo::Load,
+        SystemInfo::Pressure,
+        SystemInfo::Sensors,
+        SystemInfo::Host,
     ];
 }
 
-impl Default for SystemInfo {
-    fn default() -> SystemInfo {
-        SystemInfo::Processes
+impl std::fmt::Display for SystemInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SystemInfo::CPU => write!(f, "CPU info"),
+            SystemInfo::Mem => write!(f, "Mem info"),
+            SystemInfo::Processes => write!(f, "Processes monitor"),
+            SystemInfo::Disks => write!(f, "Disk usage"),
+            SystemInfo::DiskIo => write!(f, "Disk I/O"),
+            SystemInfo::Network => write!(f, "Network"),
+            SystemInfo::Sockets => write!(f, "Sockets"),
+            SystemInfo::Load => write!(f, "Load average"),
+            SystemInfo::Pressure => write!(f, "Pressure stalls"),
+            SystemInfo::Sensors => write!(f, "Sensors"),
+            SystemInfo::Host => write!(f, "System information"),
+            SystemInfo::ProcessDetail(pid) => write!(f, "Process {}", pid),
+        }
     }
 }
 
-impl std::fmt::Display for SystemInfo {
+/// How often the panes take a new snapshot from the collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshInterval(Duration);
+
+impl RefreshInterval {
+    const ALL: [RefreshInterval; 8] = [
+        RefreshInterval(Duration::from_millis(250)),
+        RefreshInterval(Duration::from_millis(500)),
+        RefreshInterval(Duration::from_secs(1)),
+        RefreshInterval(Duration::from_secs(2)),
+        RefreshInterval(Duration::from_secs(5)),
+        RefreshInterval(Duration::from_secs(10)),
+        RefreshInterval(Duration::from_secs(30)),
+        RefreshInterval(Duration::from_secs(60)),
+    ];
+}
+
+impl Default for RefreshInterval {
+    fn default() -> Self {
+        RefreshInterval(Duration::from_secs(1))
+    }
+}
+
+impl std::fmt::Display for RefreshInterval {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                SystemInfo::CPU => "CPU info",
-                SystemInfo::Mem => "Mem info",
-                SystemInfo::Processes => "Processes monitor",
-                SystemInfo::Uname => "System information"
-            }
-        )
+        if self.0 < Duration::from_secs(1) {
+            write!(f, "{} ms", self.0.as_millis())
+        } else {
+            write!(f, "{} s", self.0.as_secs())
+        }
+    }
+}
+
+/// Refresh interval of a single pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaneRefresh {
+    /// Follow the interval set for the whole window
+    #[default]
+    Global,
+    Every(RefreshInterval),
+}
+
+impl PaneRefresh {
+    const ALL: [PaneRefresh; 9] = [
+        PaneRefresh::Global,
+        PaneRefresh::Every(RefreshInterval::ALL[0]),
+        PaneRefresh::Every(RefreshInterval::ALL[1]),
+        PaneRefresh::Every(RefreshInterval::ALL[2]),
+        PaneRefresh::Every(RefreshInterval::ALL[3]),
+        PaneRefresh::Every(RefreshInterval::ALL[4]),
+        PaneRefresh::Every(RefreshInterval::ALL[5]),
+        PaneRefresh::Every(RefreshInterval::ALL[6]),
+        PaneRefresh::Every(RefreshInterval::ALL[7]),
+    ];
+
+    fn interval(&self, global: RefreshInterval) -> Duration {
+        match self {
+            PaneRefresh::Global => global.0,
+            PaneRefresh::Every(interval) => interval.0,
+        }
+    }
+}
+
+impl std::fmt::Display for PaneRefresh {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaneRefresh::Global => write!(f, "Global rate"),
+            PaneRefresh::Every(interval) => write!(f, "Every {}", interval),
+        }
     }
 }
 
@@ -325,6 +728,24 @@ struct Pane {
     pub is_pinned: bool,
     pub selected_info: SystemInfo,
     pub cpu_chart: SystemChart,
+    pub mem_chart: MemChart,
+    pub disk_io: disk_io::DiskIoChart,
+    pub network: network::NetworkChart,
+    pub load: load::LoadChart,
+    pub pressure: pressure::PressureChart,
+    pub sensors: sensors::SensorsChart,
+    pub socket_filter: sockets::SocketFilter,
+    pub process_table: ProcessTable,
+    /// Pane opened to show the details of the process clicked in this one
+    pub detail_pane: Option<pane_grid::Pane>,
+    pub refresh: PaneRefresh,
+    /// Whether the disk list includes tmpfs, proc and the like
+    pub show_pseudo_fs: bool,
+    /// Part of the history shown by the charts of the pane
+    pub time_window: TimeWindow,
+    /// Snapshot shown by this pane, which lags behind the latest one when
+    /// the pane refreshes less often than the collector samples
+    pub snapshot: Option<Arc<collector::Snapshot>>,
 }
 
 impl Pane {
@@ -332,52 +753,177 @@ impl Pane {
         Self {
             is_pinned: false,
             selected_info: SystemInfo::default(),
-            cpu_chart: SystemChart::default()
+            cpu_chart: SystemChart::default(),
+            mem_chart: MemChart::default(),
+            disk_io: disk_io::DiskIoChart::default(),
+            network: network::NetworkChart::default(),
+            load: load::LoadChart::default(),
+            pressure: pressure::PressureChart::default(),
+            sensors: sensors::SensorsChart::default(),
+            socket_filter: sockets::SocketFilter::default(),
+            process_table: ProcessTable::default(),
+            detail_pane: None,
+            refresh: PaneRefresh::default(),
+            show_pseudo_fs: false,
+            time_window: TimeWindow::default(),
+            snapshot: None,
+        }
+    }
+
+    /// Whether enough time has passed since the snapshot shown by the pane
+    /// for it to take `time`'s. Snapshots arrive every `sample_interval`
+    /// give or take some jitter, so the pane takes the one closest to its
+    /// own interval.
+    fn is_due(&self, time: DateTime<Utc>, global: RefreshInterval, sample_interval: Duration) -> bool {
+        let shown = match &self.snapshot {
+            Some(snapshot) => snapshot.time,
+            None => return true,
+        };
+        let elapsed = (time - shown).to_std().unwrap_or_default();
+        elapsed + sample_interval / 2 >= self.refresh.interval(global)
+    }
+
+    fn set_time_window(&mut self, time_window: TimeWindow) {
+        if time_window != self.time_window {
+            self.time_window = time_window;
+            self.cpu_chart.set_time_window(time_window);
+            self.mem_chart.set_time_window(time_window);
+            self.disk_io.set_time_window(time_window);
+            self.network.set_time_window(time_window);
+            self.load.set_time_window(time_window);
+            self.pressure.set_time_window(time_window);
+            self.sensors.set_time_window(time_window);
+        }
+    }
+
+    /// Shows `info` instead of the current view. Only the shown view
+    /// keeps its history up to date, so the history of the one it
+    /// replaces is dropped rather than left to go stale.
+    fn select_info(&mut self, info: SystemInfo, cpu_history: &CPUHistory) {
+        if info == self.selected_info {
+            return;
+        }
+        match self.selected_info {
+            SystemInfo::Mem => self.mem_chart.clear_history(),
+            SystemInfo::Processes => self.process_table.clear_history(),
+            SystemInfo::DiskIo => self.disk_io.clear_history(),
+            SystemInfo::Network => self.network.clear_history(),
+            SystemInfo::Load => self.load.clear_history(),
+            SystemInfo::Pressure => self.pressure.clear_history(),
+            SystemInfo::Sensors => self.sensors.clear_history(),
+            _ => {}
+        }
+        self.selected_info = info;
+
+        // Start from the snapshot the pane is at rather than the next one
+        if let Some(snapshot) = self.snapshot.clone() {
+            self.refresh(&snapshot, cpu_history);
         }
     }
+
+    /// Takes `snapshot` into the view shown by the pane.
+    fn refresh(&mut self, snapshot: &Arc<collector::Snapshot>, cpu_history: &CPUHistory) {
+        match self.selected_info {
+            SystemInfo::CPU => self.cpu_chart.show(snapshot.time, cpu_history),
+            SystemInfo::Mem => {
+                if let Some(mem) = snapshot.mem {
+                    self.mem_chart.push_data(snapshot.time, mem);
+                }
+            }
+            SystemInfo::Processes => self.process_table.update(snapshot.time, &snapshot.processes),
+            SystemInfo::DiskIo => {
+                if let Some(disk_io) = &snapshot.disk_io {
+                    self.disk_io.push_data(snapshot.time, disk_io);
+                }
+            }
+            SystemInfo::Network => {
+                if let Some(network) = &snapshot.network {
+                    self.network.push_data(snapshot.time, network);
+                }
+            }
+            SystemInfo::Load => {
+                if let Some(load) = snapshot.load {
+                    self.load.push_data(snapshot.time, load);
+                }
+            }
+            SystemInfo::Pressure => self.pressure.refresh(snapshot),
+            // Missing on virtual machines and in containers, which is shown
+            // the same as a machine without sensors
+            SystemInfo::Sensors => self
+                .sensors
+                .push_data(snapshot.time, snapshot.sensors.as_deref().unwrap_or_default()),
+            // Drawn straight from the snapshot
+            SystemInfo::Disks | SystemInfo::Sockets | SystemInfo::Host | SystemInfo::ProcessDetail(_) => {}
+        }
+        self.snapshot = Some(snapshot.clone());
+    }
 }
 
 fn view_content<'a>(
-    cpu_chart: &'a SystemChart,
-    info: SystemInfo,
+    id: pane_grid::Pane,
+    pane: &'a Pane,
+    cpu_history: &'a CPUHistory,
 ) -> Element<'a, Message> {
-    let d = proc::get_meminfo();
-    let mut data: VecDeque<&str> = d.split("\n").collect();
-    data.pop_front();
-    data.pop_back();
-    let mem_total;
-    let mem_used;
-    let swap_total;
-    let swap_used;
-    // println!("{:#?}", data[1].split(" ").collect::<Vec<&str>>());
-
-    mem_total = (data[0].split(" ").collect::<Vec<&str>>())[6].parse::<f32>().unwrap();
-    mem_used = (data[0].split(" ").collect::<Vec<&str>>())[11].parse::<f32>().unwrap();
-    swap_total = (data[1].split(" ").collect::<Vec<&str>>())[7].parse::<f32>().unwrap();
-    swap_used = (data[1].split(" ").collect::<Vec<&str>>())[14].parse::<f32>().unwrap();
-    let content_data = column![match info {
+    let snapshot = pane.snapshot.as_deref();
+    let content_data = column![match pane.selected_info {
         SystemInfo::CPU => {
             column![
-                // row![cpu_chart.view()],
-                cpu_chart.view(),
+                pane.cpu_chart.view(id, cpu_history),
             ]
         },
         SystemInfo::Mem => {
             column![
-                text(format!("Mem: {:.2}GB; Used: {:.2}GB", mem_total/1048576.0, mem_used/1048576.0)),
-                progress_bar(0.0..=mem_total, mem_used),
-                text(format!("Swap: {:.2}GB; Used: {:.2}GB", swap_total/1048576.0, swap_used/1048576.0)),
-                progress_bar(0.0..=swap_total, swap_used),
+                pane.mem_chart.view(id),
             ]
         },
         SystemInfo::Processes => {
             column![
-                text(proc::get_monitor_info())
+                pane.process_table.view(id)
             ]
         },
-        SystemInfo::Uname => {
+        SystemInfo::Disks => {
+            column![
+                disk_usage::view(id, snapshot.and_then(|s| s.disks.as_ref()), pane.show_pseudo_fs)
+            ]
+        }
+        SystemInfo::DiskIo => {
+            column![
+                pane.disk_io.view(id)
+            ]
+        }
+        SystemInfo::Network => {
+            column![
+                pane.network.view(id)
+            ]
+        }
+        SystemInfo::Sockets => {
+            column![
+                sockets::view(id, snapshot.and_then(|s| s.sockets.as_ref()), &pane.socket_filter)
+            ]
+        }
+        SystemInfo::Load => {
             column![
-                text(proc::get_uname())
+                pane.load.view(id)
+            ]
+        }
+        SystemInfo::Pressure => {
+            column![
+                pane.pressure.view(id)
+            ]
+        }
+        SystemInfo::Sensors => {
+            column![
+                pane.sensors.view(id)
+            ]
+        }
+        SystemInfo::Host => {
+            column![
+                system_info::view(snapshot.map(|s| &s.host))
+            ]
+        }
+        SystemInfo::ProcessDetail(pid) => {
+            column![
+                process_detail::view(pid, snapshot.and_then(|s| s.process_details.get(&pid)))
             ]
         }
     }];
@@ -397,13 +943,67 @@ fn view_content<'a>(
         .into()
 }
 
+/// Formats a size in KiB, as found in /proc/meminfo, as GB.
+fn format_kib(kib: u64) -> String {
+    format!("{:.2}GB", kib as f64 / 1048576.0)
+}
+
+fn view_mem<'a>(mem: &proc::MemInfo) -> Column<'a, Message> {
+    let bar = |total: u64, used: u64| progress_bar(0.0..=total as f32, used as f32);
+
+    let mut col = column![
+        text(format!(
+            "Mem: {}; Used: {}; Available: {}",
+            format_kib(mem.mem_total),
+            format_kib(mem.used()),
+            format_kib(mem.available()),
+        )),
+        bar(mem.mem_total, mem.used()),
+        text(format!(
+            "Free: {}; Buffers: {}; Cache: {}; Shared: {}",
+            format_kib(mem.mem_free),
+            format_kib(mem.buffers),
+            format_kib(mem.cache()),
+            format_kib(mem.shmem),
+        )),
+        text(format!(
+            "Dirty: {}; Writeback: {}",
+            format_kib(mem.dirty),
+            format_kib(mem.writeback),
+        )),
+        text(format!(
+            "Swap: {}; Used: {}",
+            format_kib(mem.swap_total),
+            format_kib(mem.swap_used()),
+        )),
+        bar(mem.swap_total, mem.swap_used()),
+    ]
+    .spacing(5);
+
+    if mem.huge_pages_total > 0 {
+        col = col
+            .push(text(format!(
+                "Huge pages: {} of {} used ({} reserved, {} surplus, {}kB each)",
+                mem.huge_pages_used(),
+                mem.huge_pages_total,
+                mem.huge_pages_rsvd,
+                mem.huge_pages_surp,
+                mem.hugepagesize,
+            )))
+            .push(bar(mem.huge_pages_total, mem.huge_pages_used()));
+    }
+
+    col
+}
+
 fn view_controls<'a>(
     pane: pane_grid::Pane,
+    state: &Pane,
     total_panes: usize,
-    is_pinned: bool,
     is_maximized: bool,
-    info: SystemInfo,
 ) -> Element<'a, Message> {
+    let info = state.selected_info;
+
     let mut row = row![].spacing(5);
 
     if total_panes > 1 {
@@ -433,6 +1033,48 @@ fn view_controls<'a>(
 
     row = row.push(controls);
 
+    if matches!(
+        info,
+        SystemInfo::CPU
+            | SystemInfo::Mem
+            | SystemInfo::DiskIo
+            | SystemInfo::Network
+            | SystemInfo::Load
+            | SystemInfo::Pressure
+            | SystemInfo::Sensors
+    ) {
+        row = row.push(
+            pick_list(&HistoryWindow::ALL[..], Some(state.time_window.length), move |length| {
+                Message::HistoryWindowSelected(pane, length)
+            })
+            .text_size(14),
+        );
+    }
+
+    if state.time_window.is_scrolled() {
+        row = row.push(
+            button(text("Live").size(14))
+                .padding(3)
+                .on_press(Message::ChartScrolled(pane, history::Scroll::Live)),
+        );
+    }
+
+    if info == SystemInfo::CPU {
+        row = row.push(
+            pick_list(&CPUView::ALL[..], Some(state.cpu_chart.view), move |view| {
+                Message::CPUViewSelected(pane, view)
+            })
+            .text_size(14),
+        );
+    }
+
+    row = row.push(
+        pick_list(&PaneRefresh::ALL[..], Some(state.refresh), move |refresh| {
+            Message::PaneRefreshSelected(pane, refresh)
+        })
+        .text_size(14),
+    );
+
     let hsplit = button(text("H+").size(14))
         .style(theme::Button::Secondary)
         .padding(3)
@@ -459,7 +1101,7 @@ fn view_controls<'a>(
         .style(theme::Button::Destructive)
         .padding(3);
 
-    if total_panes > 1 && !is_pinned {
+    if total_panes > 1 && !state.is_pinned {
         close = close.on_press(Message::Close(pane));
     }
 
@@ -517,142 +1159,447 @@ use plotters::prelude::ChartBuilder;
 use plotters_backend::DrawingBackend;
 use plotters_iced::{Chart, ChartWidget, plotters_backend};
 
+/// How the per-core usage is laid out in a CPU pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CPUView {
+    /// One chart per core
+    #[default]
+    Grid,
+    /// One row per core in a single heatmap
+    Heatmap,
+}
+
+impl CPUView {
+    const ALL: [CPUView; 2] = [CPUView::Grid, CPUView::Heatmap];
+}
+
+impl std::fmt::Display for CPUView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CPUView::Grid => "Per-core charts",
+                CPUView::Heatmap => "Heatmap",
+            }
+        )
+    }
+}
+
+/// Usage history of the aggregate and of every core. There is a single
+/// one for the whole application, so a newly opened CPU pane starts with
+/// the full history.
+#[derive(Default)]
+struct CPUHistory {
+    processors: Vec<CPUSeries>,
+}
+
+impl CPUHistory {
+    #[inline]
+    fn is_initialized(&self) -> bool {
+        !self.processors.is_empty()
+    }
+
+    fn newest_time(&self) -> Option<DateTime<Utc>> {
+        self.processors
+            .iter()
+            .filter_map(|p| p.data_points.front())
+            .map(|(time, _)| *time)
+            .max()
+    }
+
+    fn push_sample(&mut self, now: DateTime<Utc>, cpu_usage: &proc::CpuUsage, cpu_freq: &[proc::CpuFreq]) {
+        let mhz = |khz: u64| khz as f64 / 1000.0;
+        // The aggregate shows the average frequency of the cores
+        let current: Vec<f64> = cpu_freq.iter().filter_map(|f| f.current).map(mhz).collect();
+        let average = (!current.is_empty()).then(|| current.iter().sum::<f64>() / current.len() as f64);
+
+        let data = std::iter::once((None, cpu_usage.total, average, None)).chain(
+            cpu_usage.cores.iter().map(|(core, usage)| {
+                let freq = cpu_freq.iter().find(|f| f.core == *core);
+                (Some(*core), *usage, freq.and_then(|f| f.current).map(mhz), freq.cloned())
+            }),
+        );
+
+        // Rebuild the list from the sample so that charts follow cores
+        // going online or offline
+        let mut processors = Vec::with_capacity(cpu_usage.cores.len() + 1);
+        for (core, usage, current, freq) in data {
+            let mut p = match self.processors.iter().position(|p| p.core == core) {
+                Some(i) => self.processors.swap_remove(i),
+                None => CPUSeries::new(core),
+            };
+            p.push_data(now, usage, current, freq);
+            processors.push(p);
+        }
+        self.processors = processors;
+    }
+}
+
+/// How a CPU pane lays out the shared `CPUHistory`. Each pane draws the
+/// history up to the time of its own last refresh, so it keeps its own
+/// chart caches.
 struct SystemChart {
-    last_sample_time: Instant,
     items_per_row: usize,
-    processors: Vec<CPUChart>,
     chart_height: u16,
-    last_idle: Vec<f64>,
-    last_total: Vec<f64>,
+    view: CPUView,
+    time_window: TimeWindow,
+    /// Time of the newest sample shown, `None` to show all of them
+    shown_until: Option<DateTime<Utc>>,
+    /// One per entry of `CPUHistory::processors`
+    caches: Vec<Cache>,
+    heatmap_cache: Cache,
 }
 
 impl Default for SystemChart {
     fn default() -> Self {
         Self {
-            last_sample_time: Instant::now(),
             items_per_row: 1,
-            processors: Default::default(),
             chart_height: 300,
-            last_idle: Vec::new(),
-            last_total: Vec::new(),
+            view: CPUView::default(),
+            time_window: TimeWindow::default(),
+            shown_until: None,
+            caches: Vec::new(),
+            heatmap_cache: Cache::new(),
         }
     }
 }
 
 impl SystemChart {
-    #[inline]
-    fn is_initialized(&self) -> bool {
-        !self.processors.is_empty()
+    /// Shows the history up to `time`.
+    fn show(&mut self, time: DateTime<Utc>, history: &CPUHistory) {
+        self.shown_until = Some(time);
+        self.clear_caches();
+        self.sync_caches(history);
     }
 
-    #[inline]
-    fn should_update(&self) -> bool {
-        !self.is_initialized() || self.last_sample_time.elapsed() > Duration::from_millis(1000)
+    fn set_time_window(&mut self, time_window: TimeWindow) {
+        self.time_window = time_window;
+        self.clear_caches();
     }
 
-    fn update(&mut self) {
-        if !self.should_update() {
-            return;
+    fn clear_caches(&self) {
+        for cache in &self.caches {
+            cache.clear();
         }
-        //eprintln!("refresh...");
-
-        let cpu_usage = proc::get_cpuinfo(&mut self.last_idle, &mut self.last_total);
-        self.last_sample_time = Instant::now();
-        let now = Utc::now();
-        let data = cpu_usage.iter().map(|v| *v as i32);
+        self.heatmap_cache.clear();
+    }
 
-        //check if initialized
-        if !self.is_initialized() {
-            // eprintln!("init...");
-            let mut processors: Vec<_> = data
-                .map(|percent| CPUChart::new(vec![(now, percent)].into_iter()))
-                .collect();
-            self.processors.append(&mut processors);
-        } else {
-            //eprintln!("update...");
-            for (percent, p) in data.zip(self.processors.iter_mut()) {
-                p.push_data(now, percent);
-            }
+    /// Makes sure there is a cache for every chart of `history`. The
+    /// caches are indexed by position, so they are all dropped when cores
+    /// come and go.
+    fn sync_caches(&mut self, history: &CPUHistory) {
+        if self.caches.len() != history.processors.len() {
+            self.caches = history.processors.iter().map(|_| Cache::new()).collect();
+            self.heatmap_cache.clear();
         }
     }
 
-    fn view(&self) -> Element<Message> {
-        if !self.is_initialized() {
-            Text::new("Loading...")
+    fn charts<'a>(&'a self, history: &'a CPUHistory) -> impl Iterator<Item = CPUChart<'a>> {
+        let until = self.shown_until.or_else(|| history.newest_time()).unwrap_or_else(Utc::now);
+        let range = self.time_window.range(until);
+        history
+            .processors
+            .iter()
+            .zip(self.caches.iter())
+            .map(move |(series, cache)| CPUChart { series, cache, range: range.clone() })
+    }
+
+    fn view<'a>(&'a self, pane: pane_grid::Pane, history: &'a CPUHistory) -> Element<'a, Message> {
+        if !history.is_initialized() {
+            return Text::new("Loading...")
                 .horizontal_alignment(Horizontal::Center)
                 .vertical_alignment(Vertical::Center)
-                .into()
-        } else {
-            let mut col = Column::new().width(Length::Fill).height(Length::Fill);
+                .into();
+        }
+
+        let col = match self.view {
+            CPUView::Grid => self.view_grid(pane, history),
+            CPUView::Heatmap => self.view_heatmap(pane, history),
+        };
+
+        Scrollable::new(col).height(Length::Fill).into()
+    }
+
+    fn view_grid<'a>(&'a self, pane: pane_grid::Pane, history: &'a CPUHistory) -> Column<'a, Message> {
+        let mut col = Column::new().width(Length::Fill).height(Length::Fill);
+
+        let chart_height = self.chart_height;
+        let charts: Vec<CPUChart> = self.charts(history).collect();
+        let mut idx = 0;
+        for chunk in charts.chunks(self.items_per_row) {
+            let mut row = Row::new()
+                .spacing(15)
+                .padding(20)
+                .width(Length::Fill)
+                .height(Length::Units(chart_height))
+                .align_items(Alignment::Center);
+            for item in chunk {
+                row = row.push(item.view(pane));
+                idx += 1;
+            }
+            while idx % self.items_per_row != 0 {
+                row = row.push(Space::new(Length::Fill, Length::Fill));
+                idx += 1;
+            }
+            col = col.push(row);
+        }
+
+        col
+    }
+
+    fn view_heatmap<'a>(&'a self, pane: pane_grid::Pane, history: &'a CPUHistory) -> Column<'a, Message> {
+        let charts: Vec<CPUChart> = self.charts(history).collect();
+        let cores = charts.iter().filter(|p| p.series.core.is_some()).count();
+        let heatmap_height = (cores as u16).saturating_mul(14).saturating_add(60).max(150);
 
-            let chart_height = self.chart_height;
-            let mut idx = 0;
-            for chunk in self.processors.chunks(self.items_per_row) {
-                let mut row = Row::new()
-                    .spacing(15)
+        let mut col = Column::new().width(Length::Fill).height(Length::Fill);
+
+        if let Some(total) = charts.iter().find(|p| p.series.core.is_none()) {
+            col = col.push(
+                Row::new()
                     .padding(20)
                     .width(Length::Fill)
-                    .height(Length::Units(chart_height))
-                    .align_items(Alignment::Center);
-                for item in chunk {
-                    row = row.push(item.view(idx));
-                    idx += 1;
-                }
-                while idx % self.items_per_row != 0 {
-                    row = row.push(Space::new(Length::Fill, Length::Fill));
-                    idx += 1;
-                }
-                col = col.push(row);
+                    .height(Length::Units(self.chart_height))
+                    .push(total.view(pane)),
+            );
+        }
+
+        let heatmap: Element<history::Scroll> =
+            ChartWidget::new(CPUHeatmap { charts, cache: &self.heatmap_cache })
+                .height(Length::Units(heatmap_height))
+                .into();
+
+        col.push(
+            Container::new(heatmap.map(move |scroll| Message::ChartScrolled(pane, scroll)))
+            .padding(20)
+            .width(Length::Fill),
+        )
+    }
+}
+
+/// Utilization of every core over time, one row per core.
+struct CPUHeatmap<'a> {
+    charts: Vec<CPUChart<'a>>,
+    cache: &'a Cache,
+}
+
+impl Chart<history::Scroll> for CPUHeatmap<'_> {
+    type State = keyboard::Modifiers;
+
+    #[inline]
+    fn draw<F: Fn(&mut Frame)>(&self, bounds: Size, draw_fn: F) -> Geometry {
+        self.cache.draw(bounds, draw_fn)
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: canvas::Cursor,
+    ) -> (event::Status, Option<history::Scroll>) {
+        history::scroll_event(state, event, bounds, cursor)
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
+        use plotters::{prelude::*, style::Color};
+
+        // Blue when idle, through green and yellow, up to red when saturated
+        fn heat_color(percent: f64) -> HSLColor {
+            let percent = percent.clamp(0.0, 100.0) / 100.0;
+            HSLColor((1.0 - percent) * 240.0 / 360.0, 0.8, 0.5)
+        }
+
+        let cores: Vec<&CPUChart> = self
+            .charts
+            .iter()
+            .filter(|p| p.series.core.is_some())
+            .collect();
+
+        let range = match cores.first() {
+            Some(p) => p.range.clone(),
+            None => return,
+        };
+
+        let mut chart = builder
+            .x_label_area_size(0)
+            .y_label_area_size(50)
+            .margin(5)
+            .build_cartesian_2d(range.clone(), -0.5..cores.len().max(1) as f64 - 0.5)
+            .expect("failed to build chart");
+
+        let label_for = |y: &f64| {
+            let row = y.round();
+            if (y - row).abs() > f64::EPSILON || row < 0.0 {
+                return String::new();
+            }
+            match cores.get(row as usize).and_then(|p| p.series.core) {
+                Some(core) => format!("CPU{}", core),
+                None => String::new(),
             }
+        };
 
-            Scrollable::new(col).height(Length::Fill).into()
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .axis_style(ShapeStyle::from(plotters::style::colors::BLUE.mix(0.45)).stroke_width(1))
+            .y_labels(cores.len().min(16))
+            .y_label_style(
+                ("sans-serif", 12)
+                    .into_font()
+                    .color(&plotters::style::colors::BLUE.mix(0.65)),
+            )
+            .y_label_formatter(&label_for)
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        for (row, p) in cores.iter().enumerate() {
+            let y = row as f64;
+            // Buckets are newest first, so each pair spans one bucket
+            let buckets = history::downsample(p.series.data_points.iter(), &range, |u| u.busy());
+            let cells = buckets
+                .iter()
+                .zip(buckets.iter().skip(1))
+                .map(|(bucket, previous)| {
+                    Rectangle::new(
+                        [(previous.time, y - 0.5), (bucket.time, y + 0.5)],
+                        heat_color(bucket.avg).filled(),
+                    )
+                });
+
+            chart
+                .draw_series(cells)
+                .expect("failed to draw chart data");
         }
     }
 }
 
-// #[derive(Debug, Clone, Copy)]
-struct CPUChart {
-    cache: Cache,
-    data_points: VecDeque<(DateTime<Utc>, i32)>,
+/// Usage history of the aggregate or of a single core.
+struct CPUSeries {
+    core: Option<usize>,
+    data_points: VecDeque<(DateTime<Utc>, proc::CpuBreakdown)>,
+    /// Scaling frequency in MHz, empty when the core does not report it
+    freq_points: VecDeque<(DateTime<Utc>, f64)>,
+    /// Latest frequency limits, governor and throttle counts, `None` for
+    /// the aggregate
+    freq: Option<proc::CpuFreq>,
     limit: Duration,
 }
 
-impl CPUChart {
-    pub fn new(data: impl Iterator<Item = (DateTime<Utc>, i32)>) -> Self {
-        let data_points: VecDeque<_> = data.collect();
+impl CPUSeries {
+    /// `core` is the core number, or `None` for the aggregate of all cores.
+    pub fn new(core: Option<usize>) -> Self {
         Self {
-            cache: Cache::new(),
-            data_points,
-            limit: Duration::from_secs(60 as u64),
+            core,
+            data_points: VecDeque::new(),
+            freq_points: VecDeque::new(),
+            freq: None,
+            limit: history::HISTORY_LIMIT,
         }
     }
 
-    fn push_data(&mut self, time: DateTime<Utc>, value: i32) {
-        let cur_ms = time.timestamp_millis();
+    fn push_data(
+        &mut self,
+        time: DateTime<Utc>,
+        value: proc::CpuBreakdown,
+        mhz: Option<f64>,
+        freq: Option<proc::CpuFreq>,
+    ) {
         self.data_points.push_front((time, value));
-        loop {
-            if let Some((time, _)) = self.data_points.back() {
-                let diff = Duration::from_millis((cur_ms - time.timestamp_millis()) as u64);
-                if diff > self.limit {
-                    self.data_points.pop_back();
-                    continue;
-                }
+        if let Some(mhz) = mhz {
+            self.freq_points.push_front((time, mhz));
+        }
+        self.freq = freq;
+
+        let limit = chrono::Duration::from_std(self.limit).unwrap_or_else(|_| chrono::Duration::zero());
+        while matches!(self.data_points.back(), Some((t, _)) if time - *t > limit) {
+            self.data_points.pop_back();
+        }
+        while matches!(self.freq_points.back(), Some((t, _)) if time - *t > limit) {
+            self.freq_points.pop_back();
+        }
+    }
+}
+
+/// `800 MHz` or `3.40 GHz`.
+fn format_mhz(mhz: f64) -> String {
+    if mhz < 1000.0 {
+        format!("{:.0} MHz", mhz)
+    } else {
+        format!("{:.2} GHz", mhz / 1000.0)
+    }
+}
+
+/// A `CPUSeries` as drawn by one pane, over the window it shows.
+#[derive(Clone)]
+struct CPUChart<'a> {
+    series: &'a CPUSeries,
+    cache: &'a Cache,
+    range: Range<DateTime<Utc>>,
+}
+
+impl<'a> CPUChart<'a> {
+    fn title(&self) -> String {
+        let name = match self.series.core {
+            Some(core) => format!("CPU{}", core),
+            None => String::from("All CPUs"),
+        };
+        let newest = self
+            .series
+            .data_points
+            .iter()
+            .find(|(time, _)| *time <= self.range.end);
+        let mut title = match newest {
+            Some((_, usage)) => format!("{}: {:.0}%", name, usage.busy()),
+            None => name,
+        };
+
+        let newest_mhz = self
+            .series
+            .freq_points
+            .iter()
+            .find(|(time, _)| *time <= self.range.end);
+        if let Some((_, mhz)) = newest_mhz {
+            let average = if self.series.core.is_none() { " average" } else { "" };
+            title.push_str(&format!(" at {}{}", format_mhz(*mhz), average));
+        }
+
+        if let Some(freq) = &self.series.freq {
+            let details: Vec<String> = [
+                freq.governor.clone(),
+                match (freq.min, freq.max) {
+                    (Some(min), Some(max)) => Some(format!(
+                        "{} to {}",
+                        format_mhz(min as f64 / 1000.0),
+                        format_mhz(max as f64 / 1000.0)
+                    )),
+                    _ => None,
+                },
+                freq.core_throttles.map(|throttles| format!("core throttled {}", throttles)),
+                freq.package_throttles.map(|throttles| format!("package throttled {}", throttles)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if !details.is_empty() {
+                title.push_str(&format!(" ({})", details.join(", ")));
             }
-            break;
         }
-        self.cache.clear();
+
+        title
     }
 
-    fn view(&self, idx: usize) -> Element<Message> {
+    fn view(&self, pane: pane_grid::Pane) -> Element<'a, Message> {
+        let chart: Element<history::Scroll> = ChartWidget::new(self.clone()).height(Length::Fill).into();
+
         Container::new(
             Column::new()
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .spacing(5)
-                .push(Text::new(format!("CPU{}", idx)))
-                .push(
-                    ChartWidget::new(self).height(Length::Fill)
-                ),
+                .push(Text::new(self.title()))
+                .push(chart.map(move |scroll| Message::ChartScrolled(pane, scroll))),
         )
         .width(Length::Fill)
         .height(Length::Fill)
@@ -662,38 +1609,59 @@ impl CPUChart {
     }
 }
 
-impl Chart<Message> for CPUChart {
-    type State = ();
+impl Chart<history::Scroll> for CPUChart<'_> {
+    type State = keyboard::Modifiers;
 
     #[inline]
     fn draw<F: Fn(&mut Frame)>(&self, bounds: Size, draw_fn: F) -> Geometry {
         self.cache.draw(bounds, draw_fn)
     }
 
-    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut _builder: ChartBuilder<DB>) {
-        use plotters::{prelude::*, style::Color};
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: canvas::Cursor,
+    ) -> (event::Status, Option<history::Scroll>) {
+        history::scroll_event(state, event, bounds, cursor)
+    }
 
-        const PLOT_LINE_COLOR: RGBColor = RGBColor(181, 32, 186);
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
+        use plotters::{prelude::*, style::Color};
 
-        // Acquire time range
-        let newest_time = self
-            .data_points
-            .front()
-            .unwrap_or(&(
-                chrono::DateTime::from_utc(
-                    chrono::NaiveDateTime::from_timestamp_opt(0, 0).unwrap(),
-                    chrono::Utc,
-                ),
-                0,
-            ))
-            .0;
-        let oldest_time = newest_time - chrono::Duration::seconds(60 as i64);
-        let mut chart = _builder
+        // Stacked from the bottom up, each band on top of the previous one
+        const SERIES: [ChartSeries<proc::CpuBreakdown>; 6] = [
+            ("user", RGBColor(181, 32, 186), |u| u.user),
+            ("nice", RGBColor(46, 160, 67), |u| u.nice),
+            ("system", RGBColor(220, 50, 47), |u| u.system),
+            ("irq", RGBColor(237, 140, 30), |u| u.irq),
+            ("iowait", RGBColor(38, 110, 210), |u| u.iowait),
+            ("steal", RGBColor(90, 90, 90), |u| u.steal),
+        ];
+
+        const FREQ_COLOR: RGBColor = RGBColor(20, 20, 20);
+
+        // The frequency goes on a secondary axis, scaled to the highest
+        // frequency the core may run at, so that a busy core at a low
+        // clock stands out
+        let freq_points = &self.series.freq_points;
+        let has_freq = freq_points.iter().any(|(time, _)| *time <= self.range.end);
+        let freq_max = freq_points
+            .iter()
+            .map(|(_, mhz)| *mhz)
+            .chain(self.series.freq.as_ref().and_then(|f| f.max).map(|khz| khz as f64 / 1000.0))
+            .fold(0.0, f64::max)
+            .max(1.0);
+
+        let mut chart = builder
             .x_label_area_size(0)
             .y_label_area_size(28)
+            .right_y_label_area_size(if has_freq { 60 } else { 0 })
             .margin(20)
-            .build_cartesian_2d(oldest_time..newest_time, 0..100)
-            .expect("failed to build chart");
+            .build_cartesian_2d(self.range.clone(), 0.0..100.0)
+            .expect("failed to build chart")
+            .set_secondary_coord(self.range.clone(), 0.0..freq_max * 1.05);
 
         chart
             .configure_mesh()
@@ -707,53 +1675,406 @@ impl Chart<Message> for CPUChart {
                     .color(&plotters::style::colors::BLUE.mix(0.65))
                     .transform(FontTransform::Rotate90),
             )
-            .y_label_formatter(&|y| format!("{}%", y))
+            .y_label_formatter(&|y| format!("{:.0}%", y))
             .draw()
             .expect("failed to draw chart mesh");
 
-        chart
-            .draw_series(
-                AreaSeries::new(
-                    self.data_points.iter().map(|x| (x.0, x.1 as i32)),
-                    0,
-                    PLOT_LINE_COLOR.mix(0.175),
+        draw_stacked_series(&mut chart, self.series.data_points.iter(), &self.range, &SERIES);
+
+        if has_freq {
+            chart
+                .configure_secondary_axes()
+                .axis_style(ShapeStyle::from(plotters::style::colors::BLUE.mix(0.45)).stroke_width(1))
+                .y_labels(5)
+                .label_style(
+                    ("sans-serif", 12)
+                        .into_font()
+                        .color(&plotters::style::colors::BLUE.mix(0.65)),
                 )
-                .border_style(ShapeStyle::from(PLOT_LINE_COLOR).stroke_width(2)),
-            )
+                .y_label_formatter(&|y| format_mhz(*y))
+                .draw()
+                .expect("failed to draw chart mesh");
+
+            chart
+                .draw_secondary_series(LineSeries::new(
+                    history::downsample(freq_points.iter(), &self.range, |mhz| *mhz)
+                        .into_iter()
+                        .map(|bucket| (bucket.time, bucket.avg)),
+                    ShapeStyle::from(FREQ_COLOR).stroke_width(2),
+                ))
+                .expect("failed to draw chart data")
+                .label("frequency")
+                .legend(|(x, y)| PathElement::new([(x, y), (x + 8, y)], FREQ_COLOR.stroke_width(2)));
+        }
+
+        draw_series_labels(&mut chart);
+    }
+}
+
+type TimeChart<'a, DB> = plotters::chart::ChartContext<
+    'a,
+    DB,
+    plotters::coord::cartesian::Cartesian2d<
+        plotters::coord::types::RangedDateTime<DateTime<Utc>>,
+        plotters::coord::types::RangedCoordf64,
+    >,
+>;
+
+/// Name, color and value of one series of a chart.
+type ChartSeries<T> = (&'static str, plotters::style::RGBColor, fn(&T) -> f64);
+
+/// Draws the points of `data_points` within `range` as bands stacked from
+/// the bottom up, each one starting where the previous one ended, and adds
+/// a legend for them. When the points are downsampled the bands show the
+/// average of each bucket, and the spread of the total is drawn as a
+/// shaded envelope around the top band, so short spikes remain visible.
+fn draw_stacked_series<'a, 'b, DB: DrawingBackend + 'a, T: 'b>(
+    chart: &mut TimeChart<'a, DB>,
+    data_points: impl Iterator<Item = &'b (DateTime<Utc>, T)> + Clone,
+    range: &Range<DateTime<Utc>>,
+    series: &[ChartSeries<T>],
+) {
+    use plotters::{prelude::*, style::Color};
+
+    let total = history::downsample(data_points.clone(), range, |point| {
+        series.iter().map(|(_, _, value)| value(point)).sum()
+    });
+    if total.iter().any(|bucket| bucket.min < bucket.max) {
+        let envelope: Vec<_> = total
+            .iter()
+            .map(|bucket| (bucket.time, bucket.max))
+            .chain(total.iter().rev().map(|bucket| (bucket.time, bucket.min)))
+            .collect();
+        chart
+            .draw_series(std::iter::once(Polygon::new(envelope, BLACK.mix(0.15).filled())))
+            .expect("failed to draw chart data");
+    }
+
+    let mut lower: Vec<(DateTime<Utc>, f64)> = total.iter().map(|bucket| (bucket.time, 0.0)).collect();
+    for &(name, color, value) in series {
+        let upper: Vec<(DateTime<Utc>, f64)> = history::downsample(data_points.clone(), range, value)
+            .iter()
+            .zip(lower.iter())
+            .map(|(bucket, (_, base))| (bucket.time, base + bucket.avg))
+            .collect();
+
+        let band: Vec<_> = upper.iter().chain(lower.iter().rev()).copied().collect();
+        chart
+            .draw_series(std::iter::once(Polygon::new(band, color.mix(0.35).filled())))
             .expect("failed to draw chart data");
+        chart
+            .draw_series(LineSeries::new(upper.iter().copied(), ShapeStyle::from(color).stroke_width(1)))
+            .expect("failed to draw chart data")
+            .label(name)
+            .legend(move |(x, y)| Rectangle::new([(x, y - 4), (x + 8, y + 4)], color.filled()));
+
+        lower = upper;
+    }
+}
+
+/// Draws the points of `data_points` within `range` as one line per
+/// series, with a legend. When the points are downsampled the lines show
+/// the average of each bucket, within a shaded band from its minimum to
+/// its maximum.
+fn draw_line_series<'a, 'b, DB: DrawingBackend + 'a, T: 'b>(
+    chart: &mut TimeChart<'a, DB>,
+    data_points: impl Iterator<Item = &'b (DateTime<Utc>, T)> + Clone,
+    range: &Range<DateTime<Utc>>,
+    series: &[ChartSeries<T>],
+) {
+    use plotters::{prelude::*, style::Color};
+
+    for &(name, color, value) in series {
+        let buckets = history::downsample(data_points.clone(), range, value);
+
+        if buckets.iter().any(|bucket| bucket.min < bucket.max) {
+            let envelope: Vec<_> = buckets
+                .iter()
+                .map(|bucket| (bucket.time, bucket.max))
+                .chain(buckets.iter().rev().map(|bucket| (bucket.time, bucket.min)))
+                .collect();
+            chart
+                .draw_series(std::iter::once(Polygon::new(envelope, color.mix(0.2).filled())))
+                .expect("failed to draw chart data");
+        }
+
+        chart
+            .draw_series(LineSeries::new(
+                buckets.iter().map(|bucket| (bucket.time, bucket.avg)),
+                ShapeStyle::from(color).stroke_width(2),
+            ))
+            .expect("failed to draw chart data")
+            .label(name)
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 8, y)], color.stroke_width(2)));
+    }
+}
+
+/// Draws the grid and the y axis labels of a chart of a `HistorySeries`.
+fn draw_mesh<'a, DB: DrawingBackend + 'a>(
+    chart: &mut TimeChart<'a, DB>,
+    y_labels: usize,
+    y_label_formatter: &dyn Fn(&f64) -> String,
+) {
+    use plotters::{prelude::*, style::Color};
+
+    chart
+        .configure_mesh()
+        .bold_line_style(plotters::style::colors::BLUE.mix(0.1))
+        .light_line_style(plotters::style::colors::BLUE.mix(0.05))
+        .axis_style(ShapeStyle::from(plotters::style::colors::BLUE.mix(0.45)).stroke_width(1))
+        .y_labels(y_labels)
+        .y_label_style(
+            ("sans-serif", 12)
+                .into_font()
+                .color(&plotters::style::colors::BLUE.mix(0.65)),
+        )
+        .y_label_formatter(y_label_formatter)
+        .draw()
+        .expect("failed to draw chart mesh");
+}
+
+fn draw_series_labels<'a, DB: DrawingBackend + 'a>(chart: &mut TimeChart<'a, DB>) {
+    use plotters::{prelude::*, style::Color};
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(plotters::style::colors::WHITE.mix(0.6))
+        .border_style(plotters::style::colors::BLUE.mix(0.2))
+        .label_font(("sans-serif", 12))
+        .draw()
+        .expect("failed to draw chart legend");
+}
+
+struct MemChart {
+    cache: Cache,
+    data_points: VecDeque<(DateTime<Utc>, proc::MemInfo)>,
+    limit: Duration,
+    time_window: TimeWindow,
+}
+
+impl Default for MemChart {
+    fn default() -> Self {
+        Self {
+            cache: Cache::new(),
+            data_points: VecDeque::new(),
+            limit: history::HISTORY_LIMIT,
+            time_window: TimeWindow::default(),
+        }
     }
+}
 
-    // fn draw_chart<DB: DrawingBackend>(&self, _state: &Self::State, root: DrawingArea<DB, Shift>) {
-    //     let children = root.split_evenly((2, 2));
-    //     for (i, area) in children.iter().enumerate() {
-    //         let builder = ChartBuilder::on(area);
-    //         draw_chart(builder, i + 1);
-    //     }
-    // }
-}
-
-// fn draw_chart<DB: DrawingBackend>(mut chart: ChartBuilder<DB>, power: usize) {
-//     let mut chart = chart
-//         .margin(30)
-//         .caption(format!("y=x^{}", power), ("sans-serif", 22))
-//         .x_label_area_size(30)
-//         .y_label_area_size(30)
-//         .build_cartesian_2d(-1f32..1f32, -1.2f32..1.2f32)
-//         .unwrap();
-
-//     chart
-//         .configure_mesh()
-//         .x_labels(3)
-//         .y_labels(3)
-//         .draw()
-//         .unwrap();
-
-//     chart
-//         .draw_series(LineSeries::new(
-//             (-50..=50)
-//                 .map(|x| x as f32 / 50.0)
-//                 .map(|x| (x, x.powf(power as f32))),
-//             &RED,
-//         ))
-//         .unwrap();
-// }
+impl MemChart {
+    fn push_data(&mut self, time: DateTime<Utc>, value: proc::MemInfo) {
+        self.data_points.push_front((time, value));
+
+        let limit = chrono::Duration::from_std(self.limit).unwrap_or_else(|_| chrono::Duration::zero());
+        while matches!(self.data_points.back(), Some((t, _)) if time - *t > limit) {
+            self.data_points.pop_back();
+        }
+        self.cache.clear();
+    }
+
+    fn set_time_window(&mut self, time_window: TimeWindow) {
+        self.time_window = time_window;
+        self.cache.clear();
+    }
+
+    fn clear_history(&mut self) {
+        self.data_points.clear();
+        self.cache.clear();
+    }
+
+    fn view(&self, pane: pane_grid::Pane) -> Element<'_, Message> {
+        let chart: Element<history::Scroll> = ChartWidget::new(self).height(Length::Units(300)).into();
+
+        match self.data_points.front() {
+            None => Text::new("Loading...")
+                .horizontal_alignment(Horizontal::Center)
+                .vertical_alignment(Vertical::Center)
+                .into(),
+            Some((_, mem)) => Column::new()
+                .width(Length::Fill)
+                .spacing(10)
+                .push(view_mem(mem))
+                .push(chart.map(move |scroll| Message::ChartScrolled(pane, scroll)))
+                .into(),
+        }
+    }
+}
+
+impl Chart<history::Scroll> for MemChart {
+    type State = keyboard::Modifiers;
+
+    #[inline]
+    fn draw<F: Fn(&mut Frame)>(&self, bounds: Size, draw_fn: F) -> Geometry {
+        self.cache.draw(bounds, draw_fn)
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: canvas::Cursor,
+    ) -> (event::Status, Option<history::Scroll>) {
+        history::scroll_event(state, event, bounds, cursor)
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
+        use plotters::{prelude::*, style::Color};
+
+        const SWAP_COLOR: RGBColor = RGBColor(220, 50, 47);
+
+        fn gib(kib: u64) -> f64 {
+            kib as f64 / 1048576.0
+        }
+
+        // Stacked from the bottom up. `used` is whatever is left of the
+        // total once free, buffers and cache are taken out, so the bands
+        // always add up to the total.
+        const SERIES: [ChartSeries<proc::MemInfo>; 4] = [
+            ("used", RGBColor(181, 32, 186), |m| {
+                gib(m.mem_total.saturating_sub(m.mem_free + m.buffers + m.cache()))
+            }),
+            ("buffers", RGBColor(237, 140, 30), |m| gib(m.buffers)),
+            ("cache", RGBColor(38, 110, 210), |m| gib(m.cache())),
+            ("free", RGBColor(46, 160, 67), |m| gib(m.mem_free)),
+        ];
+
+        let (newest_time, newest) = match self.data_points.front() {
+            Some(point) => *point,
+            None => return,
+        };
+        let range = self.time_window.range(newest_time);
+        let max = gib(newest.mem_total.max(newest.swap_total)).max(0.01);
+
+        let mut chart = builder
+            .x_label_area_size(0)
+            .y_label_area_size(40)
+            .margin(20)
+            .build_cartesian_2d(range.clone(), 0.0..max)
+            .expect("failed to build chart");
+
+        chart
+            .configure_mesh()
+            .bold_line_style(plotters::style::colors::BLUE.mix(0.1))
+            .light_line_style(plotters::style::colors::BLUE.mix(0.05))
+            .axis_style(ShapeStyle::from(plotters::style::colors::BLUE.mix(0.45)).stroke_width(1))
+            .y_labels(10)
+            .y_label_style(
+                ("sans-serif", 15)
+                    .into_font()
+                    .color(&plotters::style::colors::BLUE.mix(0.65))
+                    .transform(FontTransform::Rotate90),
+            )
+            .y_label_formatter(&|y| format!("{:.1}GB", y))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        draw_stacked_series(&mut chart, self.data_points.iter(), &range, &SERIES);
+
+        if newest.swap_total > 0 {
+            chart
+                .draw_series(LineSeries::new(
+                    history::downsample(self.data_points.iter(), &range, |mem| gib(mem.swap_used()))
+                        .into_iter()
+                        .map(|bucket| (bucket.time, bucket.avg)),
+                    ShapeStyle::from(SWAP_COLOR).stroke_width(2),
+                ))
+                .expect("failed to draw chart data")
+                .label("swap used")
+                .legend(|(x, y)| PathElement::new([(x, y), (x + 8, y)], SWAP_COLOR.stroke_width(2)));
+        }
+
+        draw_series_labels(&mut chart);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(1_700_000_000_000 + millis).unwrap()
+    }
+
+    fn snapshot(time: DateTime<Utc>) -> Arc<collector::Snapshot> {
+        Arc::new(collector::Snapshot {
+            time,
+            cpu: None,
+            cpu_freq: Vec::new(),
+            mem: None,
+            load: None,
+            pressure: None,
+            sensors: None,
+            cgroup_pressure: HashMap::new(),
+            disks: None,
+            disk_io: None,
+            network: None,
+            sockets: None,
+            processes: Vec::new(),
+            process_details: HashMap::new(),
+            host: proc::HostInfo::default(),
+        })
+    }
+
+    #[test]
+    fn refreshes_panes_at_their_interval() {
+        let second = Duration::from_secs(1);
+        let global = RefreshInterval(Duration::from_secs(2));
+
+        // Pane refresh, milliseconds since the shown snapshot and whether
+        // the pane takes the new one, with a snapshot every second
+        let cases = [
+            (PaneRefresh::Global, 1000, false),
+            (PaneRefresh::Global, 2000, true),
+            // Close enough given the jitter of the samples
+            (PaneRefresh::Global, 1600, true),
+            (PaneRefresh::Global, 1400, false),
+            (PaneRefresh::Every(RefreshInterval(Duration::from_secs(5))), 4000, false),
+            (PaneRefresh::Every(RefreshInterval(Duration::from_secs(5))), 5000, true),
+            (PaneRefresh::Every(RefreshInterval(Duration::from_millis(500))), 1000, true),
+            // A clock that went backwards
+            (PaneRefresh::Global, -1000, false),
+        ];
+
+        for (refresh, elapsed, expected) in cases {
+            let mut pane = Pane::new();
+            pane.refresh = refresh;
+            pane.snapshot = Some(snapshot(at(0)));
+            assert_eq!(pane.is_due(at(elapsed), global, second), expected, "{:?} {}", refresh, elapsed);
+        }
+
+        // A pane without a snapshot takes the first one
+        assert!(Pane::new().is_due(at(0), global, second));
+    }
+
+    #[test]
+    fn drops_snapshots_while_paused() {
+        let (mut grid, _) = Grid::new(collector::CollectorSettings::default());
+        let shown = |grid: &Grid| {
+            let pane_times: Vec<_> = grid
+                .panes
+                .iter()
+                .map(|(_, pane)| pane.snapshot.as_ref().map(|s| s.time))
+                .collect();
+            (grid.snapshot.as_ref().map(|s| s.time), pane_times)
+        };
+
+        grid.update(Message::Tick(snapshot(at(0))));
+        assert_eq!(shown(&grid), (Some(at(0)), vec![Some(at(0))]));
+
+        grid.update(Message::TogglePause);
+        grid.update(Message::Tick(snapshot(at(5000))));
+        assert_eq!(shown(&grid), (Some(at(0)), vec![Some(at(0))]));
+
+        grid.update(Message::TogglePause);
+        grid.update(Message::Tick(snapshot(at(10000))));
+        assert_eq!(shown(&grid), (Some(at(10000)), vec![Some(at(10000))]));
+    }
+}
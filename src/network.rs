@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use iced::alignment::Alignment;
+use iced::widget::{checkbox, column, pane_grid, text, Column, Row};
+use iced::{Element, Length};
+use plotters::prelude::ChartBuilder;
+use plotters_iced::plotters_backend::DrawingBackend;
+
+use crate::disk_usage::format_bytes;
+use crate::history::{self, HistoryChart, HistorySeries, TimeWindow};
+use crate::proc::{InterfaceInfo, NetIo};
+use crate::{draw_line_series, draw_mesh, draw_series_labels, ChartSeries, Message};
+
+const CHART_HEIGHT: u16 = 200;
+
+/// Interfaces per row of the interface selector.
+const SELECTOR_COLUMNS: usize = 6;
+
+/// What one of the charts of an interface shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NetMetric {
+    Throughput,
+    Packets,
+    Errors,
+}
+
+impl NetMetric {
+    const ALL: [NetMetric; 3] = [NetMetric::Throughput, NetMetric::Packets, NetMetric::Errors];
+
+    fn title(&self) -> &'static str {
+        match self {
+            NetMetric::Throughput => "Throughput",
+            NetMetric::Packets => "Packets per second",
+            NetMetric::Errors => "Errors and drops per second",
+        }
+    }
+
+    fn series(&self) -> &'static [ChartSeries<NetIo>] {
+        use plotters::style::RGBColor;
+
+        const RX: RGBColor = RGBColor(38, 110, 210);
+        const TX: RGBColor = RGBColor(46, 160, 67);
+
+        const THROUGHPUT: [ChartSeries<NetIo>; 2] = [
+            ("rx", RX, |n| n.rx_bytes),
+            ("tx", TX, |n| n.tx_bytes),
+        ];
+        const PACKETS: [ChartSeries<NetIo>; 2] = [
+            ("rx", RX, |n| n.rx_packets),
+            ("tx", TX, |n| n.tx_packets),
+        ];
+        const ERRORS: [ChartSeries<NetIo>; 4] = [
+            ("rx errors", RGBColor(220, 50, 47), |n| n.rx_errors),
+            ("tx errors", RGBColor(181, 32, 186), |n| n.tx_errors),
+            ("rx drops", RGBColor(237, 140, 30), |n| n.rx_drops),
+            ("tx drops", RGBColor(90, 90, 90), |n| n.tx_drops),
+        ];
+
+        match self {
+            NetMetric::Throughput => &THROUGHPUT,
+            NetMetric::Packets => &PACKETS,
+            NetMetric::Errors => &ERRORS,
+        }
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self {
+            NetMetric::Throughput => format!("{}/s", format_bytes(value as u64)),
+            NetMetric::Packets | NetMetric::Errors => format!("{:.0}", value),
+        }
+    }
+
+    /// Smallest top of the y axis, so that an idle interface does not get
+    /// a chart scaled to noise.
+    fn min_max(&self) -> f64 {
+        match self {
+            NetMetric::Throughput => 1024.0 * 1024.0,
+            NetMetric::Packets => 100.0,
+            NetMetric::Errors => 1.0,
+        }
+    }
+}
+
+/// Traffic history of one interface.
+struct InterfaceSeries {
+    name: String,
+    /// One chart per `NetMetric`
+    history: HistorySeries<NetIo, { NetMetric::ALL.len() }>,
+}
+
+/// Throughput, packet rate and errors of the network interfaces.
+#[derive(Default)]
+pub struct NetworkChart {
+    interfaces: Vec<InterfaceSeries>,
+    newest_time: Option<DateTime<Utc>>,
+    time_window: TimeWindow,
+    /// Interfaces unchecked in the selector
+    hidden: HashSet<String>,
+}
+
+/// `eth0 (up, 1000 Mbit/s, MTU 1500, 00:11:22:33:44:55)`, leaving out what
+/// the interface does not report.
+fn describe_interface(name: &str, info: &InterfaceInfo) -> String {
+    let details: Vec<String> = [
+        info.operstate.clone(),
+        info.speed.map(|speed| format!("{} Mbit/s", speed)),
+        info.mtu.map(|mtu| format!("MTU {}", mtu)),
+        info.address.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if details.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} ({})", name, details.join(", "))
+    }
+}
+
+impl NetworkChart {
+    pub fn push_data(&mut self, time: DateTime<Utc>, interfaces: &[NetIo]) {
+        for interface in interfaces {
+            let series = match self.interfaces.iter().position(|s| s.name == interface.name) {
+                Some(i) => &mut self.interfaces[i],
+                None => {
+                    self.interfaces.push(InterfaceSeries {
+                        name: interface.name.clone(),
+                        history: HistorySeries::default(),
+                    });
+                    self.interfaces.last_mut().unwrap()
+                }
+            };
+            series.history.push(time, interface.clone());
+        }
+
+        for series in &mut self.interfaces {
+            series.history.trim(time);
+        }
+        // Forget interfaces that went away
+        self.interfaces.retain(|series| !series.history.is_empty());
+        self.newest_time = Some(time);
+    }
+
+    /// Forgets the interfaces and their history, keeping the selection.
+    pub fn clear_history(&mut self) {
+        self.interfaces.clear();
+        self.newest_time = None;
+    }
+
+    pub fn set_time_window(&mut self, time_window: TimeWindow) {
+        self.time_window = time_window;
+        for series in &self.interfaces {
+            series.history.clear_caches();
+        }
+    }
+
+    pub fn set_shown(&mut self, interface: String, shown: bool) {
+        if shown {
+            self.hidden.remove(&interface);
+        } else {
+            self.hidden.insert(interface);
+        }
+    }
+
+    fn view_selector(&self, pane: pane_grid::Pane) -> Column<'_, Message> {
+        self.interfaces
+            .chunks(SELECTOR_COLUMNS)
+            .fold(Column::new().spacing(5), |col, chunk| {
+                col.push(chunk.iter().fold(Row::new().spacing(10), |row, series| {
+                    let name = series.name.clone();
+                    row.push(
+                        checkbox(&series.name, !self.hidden.contains(&series.name), move |shown| {
+                            Message::NetworkInterfaceToggled(pane, name.clone(), shown)
+                        })
+                        .size(14)
+                        .text_size(14),
+                    )
+                }))
+            })
+    }
+
+    fn view_interface<'a>(
+        &self,
+        pane: pane_grid::Pane,
+        series: &'a InterfaceSeries,
+        newest_time: DateTime<Utc>,
+    ) -> Column<'a, Message> {
+        let range = self.time_window.range(newest_time);
+
+        let (title, summary) = match series.history.at(range.end) {
+            Some(interface) => (
+                describe_interface(&series.name, &interface.info),
+                format!(
+                    "rx {}/s, tx {}/s",
+                    format_bytes(interface.rx_bytes as u64),
+                    format_bytes(interface.tx_bytes as u64),
+                ),
+            ),
+            None => (series.name.clone(), String::new()),
+        };
+
+        let charts = NetMetric::ALL
+            .iter()
+            .zip(series.history.caches().iter())
+            .fold(Row::new().spacing(15), |row, (metric, cache)| {
+                let chart = history::view_chart(
+                    NetMetricChart {
+                        series,
+                        metric: *metric,
+                        range: range.clone(),
+                    },
+                    cache,
+                );
+
+                row.push(
+                    column![
+                        text(metric.title()).size(14),
+                        chart.map(move |scroll| Message::ChartScrolled(pane, scroll)),
+                    ]
+                    .width(Length::Fill)
+                    .height(Length::Units(CHART_HEIGHT))
+                    .spacing(5)
+                    .align_items(Alignment::Center),
+                )
+            });
+
+        column![text(title).size(16), text(summary).size(14), charts].spacing(10)
+    }
+
+    pub fn view(&self, pane: pane_grid::Pane) -> Element<'_, Message> {
+        let newest_time = match self.newest_time {
+            Some(time) if !self.interfaces.is_empty() => time,
+            _ => return text("Loading...").into(),
+        };
+
+        self.interfaces
+            .iter()
+            .filter(|series| !self.hidden.contains(&series.name))
+            .fold(
+                column![self.view_selector(pane)].spacing(20),
+                |col, series| col.push(self.view_interface(pane, series, newest_time)),
+            )
+            .width(Length::Fill)
+            .into()
+    }
+}
+
+struct NetMetricChart<'a> {
+    series: &'a InterfaceSeries,
+    metric: NetMetric,
+    range: Range<DateTime<Utc>>,
+}
+
+impl HistoryChart for NetMetricChart<'_> {
+    fn build_chart<DB: DrawingBackend>(&self, mut builder: ChartBuilder<DB>) {
+        let series = self.metric.series();
+        let max = self
+            .series
+            .history
+            .in_range(&self.range)
+            .flat_map(|interface| series.iter().map(move |(_, _, value)| value(interface)))
+            .fold(0.0, f64::max);
+        let max = (max * 1.1).max(self.metric.min_max());
+
+        let mut chart = builder
+            .x_label_area_size(0)
+            .y_label_area_size(60)
+            .margin(10)
+            .build_cartesian_2d(self.range.clone(), 0.0..max)
+            .expect("failed to build chart");
+
+        let metric = self.metric;
+        draw_mesh(&mut chart, 5, &|y| metric.format(*y));
+
+        draw_line_series(&mut chart, self.series.history.iter(), &self.range, series);
+        draw_series_labels(&mut chart);
+    }
+}
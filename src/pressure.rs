@@ -0,0 +1,364 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use iced::alignment::Alignment;
+use iced::widget::{column, pane_grid, row, text, text_input, Column, Row};
+use iced::{Element, Length};
+use plotters::prelude::ChartBuilder;
+use plotters_iced::plotters_backend::DrawingBackend;
+
+use crate::collector::Snapshot;
+use crate::history::{self, HistoryChart, HistorySeries, TimeWindow};
+use crate::proc::{Pressure, PressureResource};
+use crate::{draw_line_series, draw_mesh, draw_series_labels, ChartSeries, Message};
+
+const CHART_HEIGHT: u16 = 200;
+
+/// Pressure of one resource at one sample, in percent of wall time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ResourceSample {
+    some_avg10: f64,
+    some_avg60: f64,
+    full_avg10: f64,
+    full_avg60: f64,
+    /// From the growth of the `total` counters since the previous sample
+    some_stall: f64,
+    full_stall: f64,
+}
+
+/// What one of the charts of a resource shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PressureMetric {
+    Averages,
+    Stall,
+}
+
+impl PressureMetric {
+    const ALL: [PressureMetric; 2] = [PressureMetric::Averages, PressureMetric::Stall];
+
+    fn title(&self) -> &'static str {
+        match self {
+            PressureMetric::Averages => "Kernel averages",
+            PressureMetric::Stall => "Stalled time since the previous sample",
+        }
+    }
+
+    fn series(&self) -> &'static [ChartSeries<ResourceSample>] {
+        use plotters::style::RGBColor;
+
+        const SOME: RGBColor = RGBColor(237, 140, 30);
+        const FULL: RGBColor = RGBColor(220, 50, 47);
+
+        const AVERAGES: [ChartSeries<ResourceSample>; 4] = [
+            ("some avg10", SOME, |s| s.some_avg10),
+            ("some avg60", RGBColor(38, 110, 210), |s| s.some_avg60),
+            ("full avg10", FULL, |s| s.full_avg10),
+            ("full avg60", RGBColor(181, 32, 186), |s| s.full_avg60),
+        ];
+        const STALL: [ChartSeries<ResourceSample>; 2] = [
+            ("some", SOME, |s| s.some_stall),
+            ("full", FULL, |s| s.full_stall),
+        ];
+
+        match self {
+            PressureMetric::Averages => &AVERAGES,
+            PressureMetric::Stall => &STALL,
+        }
+    }
+}
+
+/// Pressure history of one resource.
+struct ResourceSeries {
+    resource: PressureResource,
+    /// One chart per `PressureMetric`
+    history: HistorySeries<ResourceSample, 2>,
+}
+
+impl ResourceSeries {
+    fn new(resource: PressureResource) -> Self {
+        Self {
+            resource,
+            history: HistorySeries::default(),
+        }
+    }
+}
+
+/// Pressure stall information of the system, or of one cgroup.
+pub struct PressureChart {
+    resources: [ResourceSeries; 3],
+    /// Previous sample, which the stall times are computed from
+    last: Option<(DateTime<Utc>, Pressure)>,
+    newest_time: Option<DateTime<Utc>>,
+    time_window: TimeWindow,
+    /// Text of the cgroup field, which may not name an existing cgroup
+    cgroup: String,
+    /// Why the pressure could not be read
+    error: Option<String>,
+}
+
+impl Default for PressureChart {
+    fn default() -> Self {
+        Self {
+            resources: PressureResource::ALL.map(ResourceSeries::new),
+            last: None,
+            newest_time: None,
+            time_window: TimeWindow::default(),
+            cgroup: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl PressureChart {
+    /// Path of the selected cgroup below the cgroup2 mount, or `None` for
+    /// the system-wide pressure.
+    pub fn cgroup(&self) -> Option<&str> {
+        let cgroup = self.cgroup.trim().trim_matches('/');
+        (!cgroup.is_empty()).then_some(cgroup)
+    }
+
+    /// Starts over with the history of another cgroup when `cgroup` names
+    /// a different one.
+    pub fn set_cgroup(&mut self, cgroup: String) {
+        let previous = self.cgroup().map(String::from);
+        self.cgroup = cgroup;
+        if self.cgroup() != previous.as_deref() {
+            self.clear_history();
+        }
+    }
+
+    pub fn clear_history(&mut self) {
+        for series in &mut self.resources {
+            series.history.clear();
+        }
+        self.last = None;
+        self.newest_time = None;
+        self.error = None;
+    }
+
+    pub fn refresh(&mut self, snapshot: &Snapshot) {
+        let pressure = match self.cgroup() {
+            None => snapshot
+                .pressure
+                .ok_or_else(|| String::from("Pressure stall information is not available on this kernel")),
+            Some(cgroup) => match snapshot.cgroup_pressure.get(cgroup) {
+                Some(Ok(pressure)) => Ok(*pressure),
+                Some(Err(e)) => Err(format!("Failed to read the pressure of cgroup {}: {}", cgroup, e)),
+                // Selected after the snapshot was taken
+                None => return,
+            },
+        };
+
+        match pressure {
+            Ok(pressure) => {
+                self.error = None;
+                self.push_data(snapshot.time, pressure);
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn push_data(&mut self, time: DateTime<Utc>, pressure: Pressure) {
+        let last = self.last.replace((time, pressure));
+        // The stall times need two samples
+        let (last_time, last) = match last {
+            Some(last) => last,
+            None => return,
+        };
+        let elapsed = match (time - last_time).num_microseconds() {
+            Some(elapsed) if elapsed > 0 => elapsed as f64,
+            _ => return,
+        };
+
+        for series in &mut self.resources {
+            let now = pressure.get(series.resource);
+            let prev = last.get(series.resource);
+            // The totals are in microseconds, and restart when a cgroup is
+            // recreated under the same path
+            let stall = |now: u64, prev: u64| now.saturating_sub(prev) as f64 / elapsed * 100.0;
+
+            series.history.push(
+                time,
+                ResourceSample {
+                    some_avg10: now.some.avg10,
+                    some_avg60: now.some.avg60,
+                    full_avg10: now.full.avg10,
+                    full_avg60: now.full.avg60,
+                    some_stall: stall(now.some.total, prev.some.total),
+                    full_stall: stall(now.full.total, prev.full.total),
+                },
+            );
+        }
+        self.newest_time = Some(time);
+    }
+
+    pub fn set_time_window(&mut self, time_window: TimeWindow) {
+        self.time_window = time_window;
+        for series in &self.resources {
+            series.history.clear_caches();
+        }
+    }
+
+    fn view_resource<'a>(
+        &self,
+        pane: pane_grid::Pane,
+        series: &'a ResourceSeries,
+        newest_time: DateTime<Utc>,
+    ) -> Column<'a, Message> {
+        let range = self.time_window.range(newest_time);
+
+        let summary = match series.history.at(range.end) {
+            Some(sample) => format!(
+                "{}: some {:.2}%, full {:.2}% over the last 10s",
+                series.resource, sample.some_avg10, sample.full_avg10,
+            ),
+            None => series.resource.to_string(),
+        };
+
+        let charts = PressureMetric::ALL
+            .iter()
+            .zip(series.history.caches().iter())
+            .fold(Row::new().spacing(15), |row, (metric, cache)| {
+                let chart = history::view_chart(
+                    PressureMetricChart {
+                        series,
+                        metric: *metric,
+                        range: range.clone(),
+                    },
+                    cache,
+                );
+
+                row.push(
+                    column![
+                        text(metric.title()).size(14),
+                        chart.map(move |scroll| Message::ChartScrolled(pane, scroll)),
+                    ]
+                    .width(Length::Fill)
+                    .height(Length::Units(CHART_HEIGHT))
+                    .spacing(5)
+                    .align_items(Alignment::Center),
+                )
+            });
+
+        column![text(summary).size(16), charts].spacing(10)
+    }
+
+    pub fn view(&self, pane: pane_grid::Pane) -> Element<'_, Message> {
+        let source = match self.cgroup() {
+            Some(cgroup) => format!("Cgroup /{}", cgroup),
+            None => String::from("System-wide"),
+        };
+        let controls = row![
+            text_input("Cgroup, such as system.slice", &self.cgroup, move |cgroup| {
+                Message::PressureCgroupChanged(pane, cgroup)
+            })
+            .padding(5)
+            .size(14)
+            .width(Length::Units(300)),
+            text(source).size(14),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        if let Some(error) = &self.error {
+            return column![controls, text(error).size(14)].spacing(20).into();
+        }
+        let newest_time = match self.newest_time {
+            Some(time) => time,
+            None => return column![controls, text("Loading...")].spacing(20).into(),
+        };
+
+        self.resources
+            .iter()
+            .fold(column![controls].spacing(20), |col, series| {
+                col.push(self.view_resource(pane, series, newest_time))
+            })
+            .width(Length::Fill)
+            .into()
+    }
+}
+
+struct PressureMetricChart<'a> {
+    series: &'a ResourceSeries,
+    metric: PressureMetric,
+    range: Range<DateTime<Utc>>,
+}
+
+impl HistoryChart for PressureMetricChart<'_> {
+    fn build_chart<DB: DrawingBackend>(&self, mut builder: ChartBuilder<DB>) {
+        let series = self.metric.series();
+        let max = self
+            .series
+            .history
+            .in_range(&self.range)
+            .flat_map(|sample| series.iter().map(move |(_, _, value)| value(sample)))
+            .fold(0.0, f64::max);
+        let max = (max * 1.1).clamp(1.0, 100.0);
+
+        let mut chart = builder
+            .x_label_area_size(0)
+            .y_label_area_size(50)
+            .margin(10)
+            .build_cartesian_2d(self.range.clone(), 0.0..max)
+            .expect("failed to build chart");
+
+        draw_mesh(&mut chart, 5, &|y| format!("{:.1}%", y));
+
+        draw_line_series(&mut chart, self.series.history.iter(), &self.range, series);
+        draw_series_labels(&mut chart);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::proc::{PressureLine, ResourcePressure};
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    /// Pressure with the same `some` and `full` totals for every resource.
+    fn pressure(some: u64, full: u64) -> Pressure {
+        let line = |total| PressureLine { total, ..PressureLine::default() };
+        let resource = ResourcePressure { some: line(some), full: line(full) };
+        Pressure { cpu: resource, memory: resource, io: resource }
+    }
+
+    /// Some and full stall of every resource at `time`.
+    fn stalls(chart: &PressureChart, time: DateTime<Utc>) -> Vec<Option<(f64, f64)>> {
+        chart
+            .resources
+            .iter()
+            .map(|series| series.history.at(time).map(|s| (s.some_stall, s.full_stall)))
+            .collect()
+    }
+
+    #[test]
+    fn computes_stall_time_between_samples() {
+        let mut chart = PressureChart::default();
+
+        // Nothing to compare the first sample with
+        chart.push_data(at(0), pressure(1_000_000, 400_000));
+        assert_eq!(chart.newest_time, None);
+
+        // A quarter of the two seconds for some, a tenth for full
+        chart.push_data(at(2), pressure(1_500_000, 600_000));
+        assert_eq!(chart.newest_time, Some(at(2)));
+        assert_eq!(stalls(&chart, at(2)), vec![Some((25.0, 10.0)); 3]);
+
+        // Taken at the same time, so skipped
+        chart.push_data(at(2), pressure(1_600_000, 600_000));
+        assert_eq!(chart.newest_time, Some(at(2)));
+
+        // The cgroup was recreated and its totals started over
+        chart.push_data(at(3), pressure(200_000, 0));
+        assert_eq!(stalls(&chart, at(3)), vec![Some((0.0, 0.0)); 3]);
+
+        // And it goes on from the new totals
+        chart.push_data(at(4), pressure(700_000, 100_000));
+        assert_eq!(stalls(&chart, at(4)), vec![Some((50.0, 10.0)); 3]);
+    }
+}
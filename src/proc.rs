@@ -1,73 +1,496 @@
-use std::{process::{Command, Stdio}, collections::VecDeque};
-
-pub fn get_cpuinfo(last_idle: &mut Vec<f64>, last_total: &mut Vec<f64>) -> Vec<f64> {
-    let cat_proc_stat = Command::new("cat")
-        .arg("/proc/stat")
-        .stdout(Stdio::piped())
-        .spawn()
-        .unwrap();
-
-    let grep = Command::new("grep")
-        .arg("cpu")
-        .stdin(Stdio::from(cat_proc_stat.stdout.unwrap()))
-        .stdout(Stdio::piped())
-        .spawn()
-        .unwrap();
-
-    let data = String::from_utf8(grep.wait_with_output().unwrap().stdout).unwrap();
-
-    let mut v: Vec<&str> = data.split("\n").collect();
-    let mut usage_data = vec![];
-    v.pop();
-
-    if v.len() > last_idle.len() {
-        last_idle.resize(v.len(), 0.0);
-        last_total.resize(v.len(), 0.0);
-    }
-
-    for i in 1..v.len() {
-        let mut d: VecDeque<&str> = v[i].split(" ").collect();
-        d.pop_front();
-        let mut sum = 0.0;
-        for i in &d {
-            if let Some(f) = i.to_string().parse::<f64>().ok() {
-                sum += f;
-            }
+use std::{fs, io, process::Command};
+
+mod cpufreq;
+mod disk;
+mod host;
+mod load;
+mod network;
+mod pressure;
+mod process;
+mod process_detail;
+mod sensors;
+mod socket;
+pub use cpufreq::*;
+pub use disk::*;
+pub use host::*;
+pub use load::*;
+pub use network::*;
+pub use pressure::*;
+pub use process::*;
+pub use process_detail::*;
+pub use sensors::*;
+pub use socket::*;
+
+/// Time spent by a CPU in each state, in USER_HZ ticks, as reported by one
+/// `cpu` line of /proc/stat.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+    pub guest: u64,
+    pub guest_nice: u64,
+}
+
+impl CpuTimes {
+    /// Total ticks. `guest` and `guest_nice` are already accounted for in
+    /// `user` and `nice`, so they are left out.
+    pub fn total_time(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    /// Share of the time between a previous sample and this one that was
+    /// spent in each state.
+    pub fn breakdown_since(&self, last: &CpuTimes) -> CpuBreakdown {
+        let total_delta = self.total_time().saturating_sub(last.total_time());
+        if total_delta == 0 {
+            return CpuBreakdown::default();
+        }
+        let percent = |now: u64, last: u64| now.saturating_sub(last) as f64 / total_delta as f64 * 100.0;
+
+        CpuBreakdown {
+            user: percent(self.user, last.user),
+            nice: percent(self.nice, last.nice),
+            system: percent(self.system, last.system),
+            iowait: percent(self.iowait, last.iowait),
+            irq: percent(self.irq + self.softirq, last.irq + last.softirq),
+            steal: percent(self.steal, last.steal),
+        }
+    }
+}
+
+/// Percentage of a sampling interval spent in each CPU state. Whatever is
+/// left up to 100% was idle.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuBreakdown {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub iowait: f64,
+    /// Hardware and software interrupts
+    pub irq: f64,
+    pub steal: f64,
+}
+
+impl CpuBreakdown {
+    /// Time the CPU was doing work. `iowait` counts as idle, since the CPU
+    /// was free to run something else.
+    pub fn busy(&self) -> f64 {
+        self.user + self.nice + self.system + self.irq + self.steal
+    }
+}
+
+/// The `cpu` lines of /proc/stat: the aggregate line and one entry per
+/// online core, tagged with the core number.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuStat {
+    pub total: CpuTimes,
+    pub cores: Vec<(usize, CpuTimes)>,
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn parse_cpu_times<'a>(mut fields: impl Iterator<Item = &'a str>) -> io::Result<CpuTimes> {
+    let mut next = || -> io::Result<u64> {
+        match fields.next() {
+            // Older kernels do not report the trailing columns
+            None => Ok(0),
+            Some(f) => f
+                .parse()
+                .map_err(|e| invalid_data(format!("bad /proc/stat value {:?}: {}", f, e))),
         }
+    };
+
+    Ok(CpuTimes {
+        user: next()?,
+        nice: next()?,
+        system: next()?,
+        idle: next()?,
+        iowait: next()?,
+        irq: next()?,
+        softirq: next()?,
+        steal: next()?,
+        guest: next()?,
+        guest_nice: next()?,
+    })
+}
 
-        let idle = d[3].to_string().parse::<f64>().unwrap();
-        let idle_delta = idle - last_idle[i];
-        let total_delta = sum - last_total[i];
-        let usage = (1.0 - (idle_delta / total_delta)) * 100.0;
-        last_total[i] = sum;
-        last_idle[i] = idle;
-        usage_data.push(usage);
+pub fn parse_cpu_stat(data: &str) -> io::Result<CpuStat> {
+    let mut stat = CpuStat::default();
+    let mut found_total = false;
+
+    for line in data.lines() {
+        let mut fields = line.split_whitespace();
+        let name = match fields.next() {
+            Some(name) if name.starts_with("cpu") => name,
+            _ => continue,
+        };
+
+        let times = parse_cpu_times(fields)?;
+        if name == "cpu" {
+            stat.total = times;
+            found_total = true;
+        } else {
+            let id = name[3..]
+                .parse()
+                .map_err(|e| invalid_data(format!("bad cpu name {:?}: {}", name, e)))?;
+            stat.cores.push((id, times));
+        }
     }
 
-    let mut res = String::from("");
-    let mut vec = vec![];
-    for u in 0..4 {
-        vec.push(usage_data[u]);
-        let new = format!("CPU{}: {}\n", u, usage_data[u]);
-        res.push_str(new.as_str());
+    if !found_total {
+        return Err(invalid_data(String::from("no cpu line in /proc/stat")));
     }
 
-    vec
+    Ok(stat)
 }
 
-pub fn get_meminfo() -> String {
-    String::from_utf8(Command::new("free").output().expect("Failed to run free -h").stdout).unwrap()
+pub fn read_cpu_stat() -> io::Result<CpuStat> {
+    parse_cpu_stat(&fs::read_to_string("/proc/stat")?)
+}
+
+/// Usage of the aggregate `cpu` line and of each online core.
+#[derive(Debug, Clone, Default)]
+pub struct CpuUsage {
+    pub total: CpuBreakdown,
+    pub cores: Vec<(usize, CpuBreakdown)>,
+}
+
+/// Usage between two samples of /proc/stat. Cores are matched by number;
+/// those that were not in `last`, having just come online, are left out.
+fn usage_since(stat: &CpuStat, last: &CpuStat) -> CpuUsage {
+    let cores = stat
+        .cores
+        .iter()
+        .filter_map(|(id, times)| {
+            let (_, last_times) = last.cores.iter().find(|(last_id, _)| last_id == id)?;
+            Some((*id, times.breakdown_since(last_times)))
+        })
+        .collect();
+
+    CpuUsage {
+        total: stat.total.breakdown_since(&last.total),
+        cores,
+    }
+}
+
+/// Samples /proc/stat and returns the usage since the times stored in
+/// `last`, which is then replaced by the new sample. Cores going online or
+/// offline between samples are handled, a core coming online being left
+/// out until the next call.
+pub fn get_cpuinfo(last: &mut CpuStat) -> io::Result<CpuUsage> {
+    let stat = read_cpu_stat()?;
+    let usage = usage_since(&stat, last);
+    *last = stat;
+
+    Ok(usage)
+}
+
+/// Fields of /proc/meminfo. Sizes are in KiB, as reported by the kernel,
+/// except for the `huge_pages_*` fields which are page counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    pub mem_total: u64,
+    pub mem_free: u64,
+    /// `None` on kernels older than 3.14, which do not report it
+    pub mem_available: Option<u64>,
+    pub buffers: u64,
+    pub cached: u64,
+    pub s_reclaimable: u64,
+    pub shmem: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub huge_pages_total: u64,
+    pub huge_pages_free: u64,
+    pub huge_pages_rsvd: u64,
+    pub huge_pages_surp: u64,
+    pub hugepagesize: u64,
+}
+
+impl MemInfo {
+    /// Page cache and reclaimable slab, which the kernel can drop on demand.
+    pub fn cache(&self) -> u64 {
+        self.cached + self.s_reclaimable
+    }
+
+    /// Memory that can be handed to new allocations without swapping.
+    pub fn available(&self) -> u64 {
+        self.mem_available
+            .unwrap_or(self.mem_free + self.buffers + self.cache())
+            .min(self.mem_total)
+    }
+
+    pub fn used(&self) -> u64 {
+        self.mem_total - self.available()
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+
+    pub fn huge_pages_used(&self) -> u64 {
+        self.huge_pages_total.saturating_sub(self.huge_pages_free)
+    }
 }
 
-pub fn get_uname() -> String {
-    String::from_utf8(Command::new("uname").arg("-a").output().expect("Failed to run uname -a").stdout).unwrap()
+pub fn parse_meminfo(data: &str) -> io::Result<MemInfo> {
+    let mut info = MemInfo::default();
+    let mut found_total = false;
+
+    for line in data.lines() {
+        let (key, value) = match line.split_once(':') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        // The unit, when present, is always kB
+        let value = match value.split_whitespace().next() {
+            Some(value) => value
+                .parse()
+                .map_err(|e| invalid_data(format!("bad /proc/meminfo value for {}: {}", key, e)))?,
+            None => continue,
+        };
+
+        match key {
+            "MemTotal" => {
+                info.mem_total = value;
+                found_total = true;
+            }
+            "MemFree" => info.mem_free = value,
+            "MemAvailable" => info.mem_available = Some(value),
+            "Buffers" => info.buffers = value,
+            "Cached" => info.cached = value,
+            "SReclaimable" => info.s_reclaimable = value,
+            "Shmem" => info.shmem = value,
+            "SwapTotal" => info.swap_total = value,
+            "SwapFree" => info.swap_free = value,
+            "Dirty" => info.dirty = value,
+            "Writeback" => info.writeback = value,
+            "HugePages_Total" => info.huge_pages_total = value,
+            "HugePages_Free" => info.huge_pages_free = value,
+            "HugePages_Rsvd" => info.huge_pages_rsvd = value,
+            "HugePages_Surp" => info.huge_pages_surp = value,
+            "Hugepagesize" => info.hugepagesize = value,
+            _ => {}
+        }
+    }
+
+    if !found_total {
+        return Err(invalid_data(String::from("no MemTotal in /proc/meminfo")));
+    }
+
+    Ok(info)
 }
 
-pub fn get_monitor_info() -> String {
-    // ps --sort -pcpu -e -o pid,uname,pcpu,pmem,time,comm
-    String::from_utf8(Command::new("ps").args(["--sort", "-pcpu", "-e", "-o", "pid,uname,pcpu,pmem,time,comm"]).output().expect("Failed to run ps -sort -pcpu -e -o pid,ppid,uname,pcpu,pmem,time,comm").stdout).unwrap()
+pub fn get_meminfo() -> io::Result<MemInfo> {
+    parse_meminfo(&fs::read_to_string("/proc/meminfo")?)
 }
 
 pub fn open_terminal() {
+    // The terminal outlives the dashboard, so it is never waited on
+    #[allow(clippy::zombie_processes)]
     Command::new("x-terminal-emulator").spawn().unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(values: [u64; 10]) -> CpuTimes {
+        let [user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice] = values;
+        CpuTimes { user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice }
+    }
+
+    #[test]
+    fn parses_cpu_stat() {
+        let cases: [(&str, Option<CpuStat>); 5] = [
+            (
+                "cpu  10 1 5 100 2 0 3 0 0 0\n\
+                 cpu0 6 1 3 50 1 0 2 0 0 0\n\
+                 cpu2 4 0 2 50 1 0 1 0 0 0\n\
+                 intr 12345 0 0\n\
+                 ctxt 6789\n",
+                Some(CpuStat {
+                    total: times([10, 1, 5, 100, 2, 0, 3, 0, 0, 0]),
+                    cores: vec![
+                        (0, times([6, 1, 3, 50, 1, 0, 2, 0, 0, 0])),
+                        (2, times([4, 0, 2, 50, 1, 0, 1, 0, 0, 0])),
+                    ],
+                }),
+            ),
+            // Kernels before 2.6.33 stop at steal, or earlier
+            (
+                "cpu 1 2 3 4 5 6 7\n",
+                Some(CpuStat {
+                    total: times([1, 2, 3, 4, 5, 6, 7, 0, 0, 0]),
+                    cores: vec![],
+                }),
+            ),
+            ("cpu0 1 2 3 4\n", None),
+            ("cpu 1 2 x 4\n", None),
+            ("cpu 1 2 3 4\ncpux 1 2 3 4\n", None),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(parse_cpu_stat(data).ok(), expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn breaks_down_cpu_time() {
+        // Last and current times, then the user, nice, system, iowait,
+        // irq + softirq and steal shares and the busy share, which adds
+        // up all but iowait
+        let cases: [([u64; 10], [u64; 10], [f64; 7]); 5] = [
+            // 200 ticks, 100 of them idle. guest is already in user.
+            (
+                [100, 10, 50, 1000, 20, 5, 5, 3, 7, 0],
+                [140, 20, 70, 1100, 30, 8, 12, 13, 37, 0],
+                [20.0, 5.0, 10.0, 5.0, 5.0, 5.0, 45.0],
+            ),
+            (
+                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [300, 0, 100, 0, 0, 0, 0, 0, 0, 0],
+                [75.0, 0.0, 25.0, 0.0, 0.0, 0.0, 100.0],
+            ),
+            // No time went by
+            (
+                [100, 10, 50, 1000, 20, 5, 5, 3, 0, 0],
+                [100, 10, 50, 1000, 20, 5, 5, 3, 0, 0],
+                [0.0; 7],
+            ),
+            // The total went backwards, as after a core went offline
+            (
+                [100, 10, 50, 1000, 20, 5, 5, 3, 0, 0],
+                [10, 1, 5, 100, 2, 0, 0, 0, 0, 0],
+                [0.0; 7],
+            ),
+            // Only user went backwards
+            (
+                [100, 0, 0, 1000, 0, 0, 0, 0, 0, 0],
+                [90, 0, 20, 1090, 0, 0, 0, 0, 0, 0],
+                [0.0, 0.0, 20.0, 0.0, 0.0, 0.0, 20.0],
+            ),
+        ];
+
+        for (last, now, expected) in cases {
+            let cpu = times(now).breakdown_since(&times(last));
+            let shares = [cpu.user, cpu.nice, cpu.system, cpu.iowait, cpu.irq, cpu.steal, cpu.busy()];
+            for (share, expected) in shares.iter().zip(expected) {
+                assert!((share - expected).abs() < 1e-9, "{:?} -> {:?}: {:?}", last, now, cpu);
+            }
+            // What is left up to 100% was idle
+            assert!(cpu.busy() + cpu.iowait <= 100.0 + 1e-9, "{:?}", cpu);
+        }
+    }
+
+    #[test]
+    fn matches_cores_by_number() {
+        let stat = |cores: &[usize], busy: u64| CpuStat {
+            total: times([busy * cores.len() as u64, 0, 0, 100, 0, 0, 0, 0, 0, 0]),
+            cores: cores.iter().map(|&id| (id, times([busy, 0, 0, 100, 0, 0, 0, 0, 0, 0]))).collect(),
+        };
+
+        // Core 1 went offline and core 3 came online
+        let last = stat(&[0, 1, 2], 100);
+        let now = stat(&[0, 2, 3], 200);
+
+        let usage = usage_since(&now, &last);
+        let cores: Vec<(usize, f64)> = usage.cores.iter().map(|(id, cpu)| (*id, cpu.user)).collect();
+        assert_eq!(cores, [(0, 100.0), (2, 100.0)]);
+
+        // It shows up once there is a sample to compare it with
+        let later = stat(&[0, 2, 3], 250);
+        let usage = usage_since(&later, &now);
+        let ids: Vec<usize> = usage.cores.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, [0, 2, 3]);
+
+        assert!(usage_since(&now, &CpuStat::default()).cores.is_empty());
+    }
+
+    #[test]
+    fn parses_meminfo() {
+        let data = "MemTotal:       16303428 kB\n\
+                    MemFree:         1203440 kB\n\
+                    MemAvailable:    9876543 kB\n\
+                    Buffers:          345678 kB\n\
+                    Cached:          7654321 kB\n\
+                    SwapCached:            0 kB\n\
+                    Shmem:            123456 kB\n\
+                    SReclaimable:     456789 kB\n\
+                    SwapTotal:       2097148 kB\n\
+                    SwapFree:        2000000 kB\n\
+                    Dirty:               128 kB\n\
+                    Writeback:             0 kB\n\
+                    HugePages_Total:       4\n\
+                    HugePages_Free:        3\n\
+                    HugePages_Rsvd:        1\n\
+                    HugePages_Surp:        0\n\
+                    Hugepagesize:       2048 kB\n";
+        let expected = MemInfo {
+            mem_total: 16303428,
+            mem_free: 1203440,
+            mem_available: Some(9876543),
+            buffers: 345678,
+            cached: 7654321,
+            s_reclaimable: 456789,
+            shmem: 123456,
+            swap_total: 2097148,
+            swap_free: 2000000,
+            dirty: 128,
+            writeback: 0,
+            huge_pages_total: 4,
+            huge_pages_free: 3,
+            huge_pages_rsvd: 1,
+            huge_pages_surp: 0,
+            hugepagesize: 2048,
+        };
+
+        let cases: [(&str, Option<MemInfo>); 4] = [
+            (data, Some(expected)),
+            // Before 3.14 there is no MemAvailable
+            (
+                "MemTotal: 1000 kB\nMemFree: 100 kB\n",
+                Some(MemInfo { mem_total: 1000, mem_free: 100, ..MemInfo::default() }),
+            ),
+            ("MemFree: 100 kB\n", None),
+            ("MemTotal: lots kB\n", None),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(parse_meminfo(data).ok(), expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn estimates_available_memory() {
+        let old_kernel = MemInfo {
+            mem_total: 1000,
+            mem_free: 100,
+            buffers: 50,
+            cached: 200,
+            s_reclaimable: 25,
+            ..MemInfo::default()
+        };
+        assert_eq!(old_kernel.available(), 375);
+        assert_eq!(old_kernel.used(), 625);
+
+        let new_kernel = MemInfo { mem_available: Some(2000), ..old_kernel };
+        assert_eq!(new_kernel.available(), 1000);
+    }
+}
@@ -0,0 +1,148 @@
+use std::{fs, path::Path};
+
+/// Where the kernel lists the CPUs.
+pub const CPU_ROOT: &str = "/sys/devices/system/cpu";
+
+/// Frequency scaling and thermal throttling of one core. Each field is
+/// `None` when the kernel does not report it, as on virtual machines
+/// without cpufreq or on CPUs without thermal throttle counters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuFreq {
+    pub core: usize,
+    /// Current, minimum and maximum scaling frequency in kHz
+    pub current: Option<u64>,
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    pub governor: Option<String>,
+    /// Times the core was throttled for running too hot since boot
+    pub core_throttles: Option<u64>,
+    /// Times the whole package the core is on was throttled, which every
+    /// core of the package reports
+    pub package_throttles: Option<u64>,
+}
+
+/// Reads the cpufreq and thermal_throttle directories of every online core
+/// under `root` that has either. `root` is `CPU_ROOT` except when pointed
+/// at a copy of sysfs.
+pub fn read_cpufreq(root: &Path) -> Vec<CpuFreq> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut cores: Vec<CpuFreq> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let core = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("cpu")?
+                .parse::<usize>()
+                .ok()?;
+            let dir = entry.path();
+            let read = |path: &str| {
+                fs::read_to_string(dir.join(path))
+                    .ok()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+            let read_number = |path: &str| read(path).and_then(|value| value.parse::<u64>().ok());
+
+            let freq = CpuFreq {
+                core,
+                current: read_number("cpufreq/scaling_cur_freq"),
+                min: read_number("cpufreq/scaling_min_freq"),
+                max: read_number("cpufreq/scaling_max_freq"),
+                governor: read("cpufreq/scaling_governor"),
+                core_throttles: read_number("thermal_throttle/core_throttle_count"),
+                package_throttles: read_number("thermal_throttle/package_throttle_count"),
+            };
+            let has_data = dir.join("cpufreq").exists()
+                || freq.core_throttles.is_some()
+                || freq.package_throttles.is_some();
+            has_data.then_some(freq)
+        })
+        .collect();
+    cores.sort_by_key(|freq| freq.core);
+
+    cores
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Fake CPU tree under the temp dir, removed when dropped.
+    struct CpuTree(PathBuf);
+
+    impl CpuTree {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("cpu-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            CpuTree(root)
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    impl Drop for CpuTree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn reads_frequency_governor_and_throttles() {
+        let tree = CpuTree::new("freq");
+        // Not cores
+        tree.write("online", "0,2,10\n");
+        tree.write("cpufreq/boost", "1\n");
+        tree.write("cpuidle/current_driver", "intel_idle\n");
+
+        tree.write("cpu0/cpufreq/scaling_cur_freq", "2400000\n");
+        tree.write("cpu0/cpufreq/scaling_min_freq", "800000\n");
+        tree.write("cpu0/cpufreq/scaling_max_freq", "4200000\n");
+        tree.write("cpu0/cpufreq/scaling_governor", "powersave\n");
+        tree.write("cpu0/thermal_throttle/core_throttle_count", "3\n");
+        tree.write("cpu0/thermal_throttle/package_throttle_count", "12\n");
+        // Offline, so the kernel removed its cpufreq directory
+        tree.write("cpu1/online", "0\n");
+        // No cpufreq driver, only the throttle counters
+        tree.write("cpu2/thermal_throttle/core_throttle_count", "0\n");
+        tree.write("cpu2/thermal_throttle/package_throttle_count", "12\n");
+        // Unreadable or empty values are left out
+        tree.write("cpu10/cpufreq/scaling_cur_freq", "<unknown>\n");
+        tree.write("cpu10/cpufreq/scaling_governor", "\n");
+
+        let freq = |core, current, min, max, governor: Option<&str>, core_throttles, package_throttles| CpuFreq {
+            core,
+            current,
+            min,
+            max,
+            governor: governor.map(String::from),
+            core_throttles,
+            package_throttles,
+        };
+        assert_eq!(
+            read_cpufreq(&tree.0),
+            [
+                freq(0, Some(2400000), Some(800000), Some(4200000), Some("powersave"), Some(3), Some(12)),
+                freq(2, None, None, None, None, Some(0), Some(12)),
+                freq(10, None, None, None, None, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_nothing_without_a_cpu_tree() {
+        let tree = CpuTree::new("missing");
+        assert!(read_cpufreq(&tree.0.join("cpu")).is_empty());
+    }
+}
@@ -0,0 +1,584 @@
+use std::{
+    collections::HashSet,
+    ffi::CString,
+    fs, io,
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::invalid_data;
+
+/// Filesystem types that are not backed by a disk, hidden from the disk
+/// list unless asked for.
+const PSEUDO_FILESYSTEMS: [&str; 26] = [
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "fuse.gvfsd-fuse",
+    "fuse.portal",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "overlay",
+    "proc",
+    "pstore",
+    "ramfs",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+];
+
+/// One line of /proc/self/mountinfo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub mount_id: u32,
+    /// `major:minor` of the device
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    /// Device or name the filesystem was mounted from
+    pub source: String,
+}
+
+impl Mount {
+    pub fn is_pseudo(&self) -> bool {
+        PSEUDO_FILESYSTEMS.contains(&self.fs_type.as_str())
+    }
+}
+
+/// Undoes the octal escaping of spaces, tabs, newlines and backslashes in
+/// the paths of /proc/self/mountinfo.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let code = bytes[i + 1..i + 4].iter().fold(0u32, |n, b| n * 8 + (b - b'0') as u32);
+            out.push(code as u8);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub fn parse_mountinfo(data: &str) -> io::Result<Vec<Mount>> {
+    let mut mounts = Vec::new();
+
+    for line in data.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        // The optional fields end with a lone "-", followed by the
+        // filesystem type and the source
+        let separator = fields
+            .iter()
+            .position(|f| *f == "-")
+            .ok_or_else(|| invalid_data(format!("no separator in mountinfo line {:?}", line)))?;
+        if separator < 6 || fields.len() < separator + 3 {
+            return Err(invalid_data(format!("bad mountinfo line {:?}", line)));
+        }
+
+        mounts.push(Mount {
+            mount_id: fields[0]
+                .parse()
+                .map_err(|e| invalid_data(format!("bad mount id in {:?}: {}", line, e)))?,
+            device: fields[2].to_string(),
+            mount_point: unescape(fields[4]),
+            fs_type: fields[separator + 1].to_string(),
+            source: unescape(fields[separator + 2]),
+        });
+    }
+
+    Ok(mounts)
+}
+
+pub fn read_mounts() -> io::Result<Vec<Mount>> {
+    parse_mountinfo(&fs::read_to_string("/proc/self/mountinfo")?)
+}
+
+/// Space and inodes of a filesystem, from statvfs. Sizes are in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FsUsage {
+    pub total: u64,
+    pub free: u64,
+    /// Free space usable by unprivileged users, without the reserved blocks
+    pub available: u64,
+    pub inodes: u64,
+    pub inodes_free: u64,
+}
+
+impl FsUsage {
+    /// Space that is taken, reserved blocks excluded.
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    /// Share of the space usable by unprivileged users that is taken, as
+    /// reported by `df`.
+    pub fn used_percent(&self) -> f64 {
+        let usable = self.used() + self.available;
+        if usable == 0 {
+            return 0.0;
+        }
+        self.used() as f64 / usable as f64 * 100.0
+    }
+
+    pub fn inodes_used(&self) -> u64 {
+        self.inodes.saturating_sub(self.inodes_free)
+    }
+}
+
+pub fn statvfs(path: &Path) -> io::Result<FsUsage> {
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL terminated and `stat` is only read on success
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stat.assume_init()
+    };
+
+    let fragment = stat.f_frsize as u64;
+    Ok(FsUsage {
+        total: stat.f_blocks as u64 * fragment,
+        free: stat.f_bfree as u64 * fragment,
+        available: stat.f_bavail as u64 * fragment,
+        inodes: stat.f_files as u64,
+        inodes_free: stat.f_ffree as u64,
+    })
+}
+
+#[derive(Debug)]
+pub struct DiskUsage {
+    pub mount: Mount,
+    pub usage: io::Result<FsUsage>,
+}
+
+/// How long statvfs may take before a filesystem is reported as not
+/// responding.
+const STATVFS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Reads the usage of the mounted filesystems. statvfs blocks for as long
+/// as the server of a network filesystem, or the daemon of a FUSE one,
+/// does not answer, so each call runs on a thread of its own and is only
+/// waited for up to `STATVFS_TIMEOUT`.
+#[derive(Default)]
+pub struct DiskUsageSampler {
+    /// Mount IDs of the filesystems whose statvfs call has not returned
+    /// yet. They are not queried again until it does, so a hung mount ties
+    /// up one thread rather than one more on every sample.
+    pending: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl DiskUsageSampler {
+    /// Usage of the mounted filesystems, in mount order. Pseudo filesystems
+    /// are left out unless `include_pseudo` is set, so they are not queried
+    /// for nothing.
+    pub fn sample(&mut self, include_pseudo: bool) -> io::Result<Vec<DiskUsage>> {
+        let mounts: Vec<Mount> = read_mounts()?
+            .into_iter()
+            .filter(|mount| include_pseudo || !mount.is_pseudo())
+            .collect();
+
+        let (sender, receiver) = mpsc::channel();
+        for (i, mount) in mounts.iter().enumerate() {
+            let (mount_id, mount_point) = (mount.mount_id, mount.mount_point.clone());
+            if !self.pending.lock().is_ok_and(|mut pending| pending.insert(mount_id)) {
+                continue;
+            }
+
+            let pending = self.pending.clone();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let usage = statvfs(Path::new(&mount_point));
+                if let Ok(mut pending) = pending.lock() {
+                    pending.remove(&mount_id);
+                }
+                // The sample may have stopped waiting already
+                let _ = sender.send((i, usage));
+            });
+        }
+        drop(sender);
+
+        let deadline = Instant::now() + STATVFS_TIMEOUT;
+        let mut usages: Vec<Option<io::Result<FsUsage>>> = mounts.iter().map(|_| None).collect();
+        // Stops once every thread has answered or the time is up
+        while let Ok((i, usage)) = receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            usages[i] = Some(usage);
+        }
+
+        Ok(mounts
+            .into_iter()
+            .zip(usages)
+            .map(|(mount, usage)| DiskUsage {
+                usage: usage.unwrap_or_else(|| Err(io::Error::new(io::ErrorKind::TimedOut, "not responding"))),
+                mount,
+            })
+            .collect())
+    }
+}
+
+/// Sectors in /proc/diskstats are always 512 bytes, whatever the device.
+const SECTOR_SIZE: u64 = 512;
+
+/// Counters of one line of /proc/diskstats. Times are in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskStats {
+    pub name: String,
+    pub reads: u64,
+    pub sectors_read: u64,
+    /// Milliseconds spent on the completed reads
+    pub read_time: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    /// Milliseconds spent on the completed writes
+    pub write_time: u64,
+    /// Requests currently queued or being serviced
+    pub in_flight: u64,
+    /// Time during which at least one request was in flight
+    pub io_time: u64,
+    /// Time requests spent in flight, summed over all requests
+    pub weighted_io_time: u64,
+}
+
+pub fn parse_diskstats(data: &str) -> io::Result<Vec<DiskStats>> {
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 14 {
+                return Err(invalid_data(format!("bad /proc/diskstats line {:?}", line)));
+            }
+            let number = |n: usize| -> io::Result<u64> {
+                fields[n]
+                    .parse()
+                    .map_err(|e| invalid_data(format!("bad /proc/diskstats value {:?}: {}", fields[n], e)))
+            };
+
+            Ok(DiskStats {
+                name: fields[2].to_string(),
+                reads: number(3)?,
+                sectors_read: number(5)?,
+                read_time: number(6)?,
+                writes: number(7)?,
+                sectors_written: number(9)?,
+                write_time: number(10)?,
+                in_flight: number(11)?,
+                io_time: number(12)?,
+                weighted_io_time: number(13)?,
+            })
+        })
+        .collect()
+}
+
+/// A sample of /proc/diskstats and when it was taken.
+#[derive(Debug, Clone)]
+pub struct DiskStatSample {
+    pub time: Instant,
+    pub disks: Vec<DiskStats>,
+}
+
+impl Default for DiskStatSample {
+    fn default() -> Self {
+        Self {
+            time: Instant::now(),
+            disks: Vec::new(),
+        }
+    }
+}
+
+pub fn read_diskstats() -> io::Result<DiskStatSample> {
+    Ok(DiskStatSample {
+        time: Instant::now(),
+        disks: parse_diskstats(&fs::read_to_string("/proc/diskstats")?)?,
+    })
+}
+
+/// Activity of a block device between two samples of /proc/diskstats.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiskIo {
+    pub name: String,
+    pub is_partition: bool,
+    /// Loop devices and RAM disks
+    pub is_virtual: bool,
+    pub read_bytes: f64,
+    pub write_bytes: f64,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    /// Average time in milliseconds the completed requests took, queueing
+    /// included, as `r_await` and `w_await` of iostat
+    pub read_await: f64,
+    pub write_await: f64,
+    /// Average number of requests in flight
+    pub queue_depth: f64,
+    /// Percentage of the time the device was busy
+    pub utilization: f64,
+}
+
+/// Per-second activity between two samples of /proc/diskstats. Devices
+/// are matched by name; those that were not in `last` are left out.
+fn diskio_since(sample: &DiskStatSample, last: &DiskStatSample) -> Vec<DiskIo> {
+    let elapsed = sample.time.duration_since(last.time).as_secs_f64();
+
+    if elapsed > 0.0 {
+        sample
+            .disks
+            .iter()
+            .filter_map(|disk| {
+                let prev = last.disks.iter().find(|prev| prev.name == disk.name)?;
+                // Counters can wrap around or reset when a device is
+                // detached and attached again
+                let rate = |now: u64, prev: u64| now.saturating_sub(prev) as f64 / elapsed;
+                let elapsed_ms = elapsed * 1000.0;
+                // Zero when no request completed
+                let wait = |time: u64, prev_time: u64, count: u64, prev_count: u64| {
+                    let count = count.saturating_sub(prev_count);
+                    if count == 0 {
+                        0.0
+                    } else {
+                        time.saturating_sub(prev_time) as f64 / count as f64
+                    }
+                };
+
+                Some(DiskIo {
+                    name: disk.name.clone(),
+                    is_partition: Path::new("/sys/class/block")
+                        .join(&disk.name)
+                        .join("partition")
+                        .exists(),
+                    is_virtual: disk.name.starts_with("loop") || disk.name.starts_with("ram"),
+                    read_bytes: rate(disk.sectors_read, prev.sectors_read) * SECTOR_SIZE as f64,
+                    write_bytes: rate(disk.sectors_written, prev.sectors_written) * SECTOR_SIZE as f64,
+                    read_iops: rate(disk.reads, prev.reads),
+                    write_iops: rate(disk.writes, prev.writes),
+                    read_await: wait(disk.read_time, prev.read_time, disk.reads, prev.reads),
+                    write_await: wait(disk.write_time, prev.write_time, disk.writes, prev.writes),
+                    queue_depth: disk.weighted_io_time.saturating_sub(prev.weighted_io_time) as f64 / elapsed_ms,
+                    utilization: (disk.io_time.saturating_sub(prev.io_time) as f64 / elapsed_ms * 100.0).min(100.0),
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Samples /proc/diskstats and returns the per-second activity since the
+/// sample in `last`, which is then replaced by the new one. Devices that
+/// were not in `last` are left out until the next call.
+pub fn get_diskio(last: &mut DiskStatSample) -> io::Result<Vec<DiskIo>> {
+    let sample = read_diskstats()?;
+    let disks = diskio_since(&sample, last);
+    *last = sample;
+
+    Ok(disks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescapes_mount_paths() {
+        let cases = [
+            ("/mnt/data", "/mnt/data"),
+            (r"/mnt/my\040disk", "/mnt/my disk"),
+            (r"/mnt/tab\011and\012newline", "/mnt/tab\tand\nnewline"),
+            (r"/mnt/back\134slash", r"/mnt/back\slash"),
+            (r"\040", " "),
+            // Not a full escape, kept as is
+            (r"/mnt/a\04", r"/mnt/a\04"),
+            (r"/mnt/a\999", r"/mnt/a\999"),
+            (r"/mnt/a\", r"/mnt/a\"),
+        ];
+
+        for (field, expected) in cases {
+            assert_eq!(unescape(field), expected, "{:?}", field);
+        }
+    }
+
+    #[test]
+    fn parses_mountinfo() {
+        let data = "\
+23 28 0:22 / /proc rw,relatime - proc proc rw
+36 28 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw,errors=remount-ro
+41 36 8:3 / /mnt/my\\040disk rw,relatime shared:30 master:2 - xfs /dev/sda3 rw
+52 36 7:0 / /snap/core/1 ro,nodev,relatime shared:40 - squashfs /dev/loop0 ro
+";
+        let mount = |mount_id, device: &str, mount_point: &str, fs_type: &str, source: &str| Mount {
+            mount_id,
+            device: device.to_string(),
+            mount_point: mount_point.to_string(),
+            fs_type: fs_type.to_string(),
+            source: source.to_string(),
+        };
+
+        let mounts = parse_mountinfo(data).unwrap();
+        assert_eq!(
+            mounts,
+            [
+                mount(23, "0:22", "/proc", "proc", "proc"),
+                mount(36, "8:2", "/", "ext4", "/dev/sda2"),
+                mount(41, "8:3", "/mnt/my disk", "xfs", "/dev/sda3"),
+                mount(52, "7:0", "/snap/core/1", "squashfs", "/dev/loop0"),
+            ]
+        );
+        let pseudo: Vec<bool> = mounts.iter().map(Mount::is_pseudo).collect();
+        assert_eq!(pseudo, [true, false, false, false]);
+    }
+
+    #[test]
+    fn rejects_bad_mountinfo() {
+        let cases = [
+            "23 28 0:22 / /proc rw,relatime proc proc rw",
+            "23 28 0:22 / - proc proc rw",
+            "23 28 0:22 / /proc rw,relatime - proc",
+            "x 28 0:22 / /proc rw,relatime - proc proc rw",
+        ];
+
+        for data in cases {
+            assert!(parse_mountinfo(data).is_err(), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn parses_diskstats() {
+        let cases: [(&str, Option<Vec<DiskStats>>); 5] = [
+            (
+                "   8       0 sda 1000 20 80000 500 2000 40 160000 900 3 1200 1400 0 0 0 0\n\
+                 \n\
+                 259       1 nvme0n1p1 10 0 80 1 20 0 160 2 0 3 4\n",
+                Some(vec![
+                    DiskStats {
+                        name: String::from("sda"),
+                        reads: 1000,
+                        sectors_read: 80000,
+                        read_time: 500,
+                        writes: 2000,
+                        sectors_written: 160000,
+                        write_time: 900,
+                        in_flight: 3,
+                        io_time: 1200,
+                        weighted_io_time: 1400,
+                    },
+                    // Kernels before 4.18 stop after the weighted time
+                    DiskStats {
+                        name: String::from("nvme0n1p1"),
+                        reads: 10,
+                        sectors_read: 80,
+                        read_time: 1,
+                        writes: 20,
+                        sectors_written: 160,
+                        write_time: 2,
+                        in_flight: 0,
+                        io_time: 3,
+                        weighted_io_time: 4,
+                    },
+                ]),
+            ),
+            ("", Some(vec![])),
+            ("8 0 sda 1000 20 80000 500 2000 40 160000 900 3 1200\n", None),
+            ("8 0 sda 1000 20 80000 500 2000 40 160000 900 3 1200 -1\n", None),
+            ("8 0 sda 1000 20 many 500 2000 40 160000 900 3 1200 1400\n", None),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(parse_diskstats(data).ok(), expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn computes_diskio_between_samples() {
+        // reads, sectors read, read time, writes, sectors written, write
+        // time, in flight, I/O time, weighted I/O time
+        let stats = |name: &str, c: [u64; 9]| DiskStats {
+            name: name.to_string(),
+            reads: c[0],
+            sectors_read: c[1],
+            read_time: c[2],
+            writes: c[3],
+            sectors_written: c[4],
+            write_time: c[5],
+            in_flight: c[6],
+            io_time: c[7],
+            weighted_io_time: c[8],
+        };
+        let last = DiskStatSample {
+            time: Instant::now(),
+            disks: vec![
+                stats("sda", [1000, 80000, 500, 2000, 160000, 900, 3, 1200, 1400]),
+                stats("sdb", [10, 80, 5, 0, 0, 0, 1, 100, 100]),
+                stats("ram0", [7, 56, 0, 0, 0, 0, 0, 0, 0]),
+                stats("loop0", [500, 4000, 300, 50, 400, 60, 0, 900, 900]),
+                stats("sdc", [1, 8, 1, 1, 8, 1, 0, 1, 1]),
+            ],
+        };
+        let sample = DiskStatSample {
+            time: last.time + Duration::from_secs(2),
+            disks: vec![
+                stats("sda", [1100, 82048, 800, 2050, 160400, 1400, 2, 2200, 3400]),
+                // Busy for longer than the interval, as reported right
+                // after a long request completes
+                stats("sdb", [12, 96, 9, 0, 0, 0, 0, 3100, 3100]),
+                // No request completed
+                stats("ram0", [7, 56, 0, 0, 0, 0, 0, 0, 0]),
+                // Detached and attached again, so the counters went back
+                stats("loop0", [5, 40, 3, 0, 0, 0, 0, 10, 10]),
+                // Not in the last sample
+                stats("nvme0n1", [1, 8, 1, 1, 8, 1, 0, 1, 1]),
+            ],
+        };
+
+        let disks = diskio_since(&sample, &last);
+        let summary: Vec<(&str, bool, [f64; 8])> = disks
+            .iter()
+            .map(|d| {
+                (
+                    d.name.as_str(),
+                    d.is_virtual,
+                    [
+                        d.read_bytes,
+                        d.write_bytes,
+                        d.read_iops,
+                        d.write_iops,
+                        d.read_await,
+                        d.write_await,
+                        d.queue_depth,
+                        d.utilization,
+                    ],
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("sda", false, [524288.0, 102400.0, 50.0, 25.0, 3.0, 10.0, 1.0, 50.0]),
+                ("sdb", false, [4096.0, 0.0, 1.0, 0.0, 2.0, 0.0, 1.5, 100.0]),
+                ("ram0", true, [0.0; 8]),
+                ("loop0", true, [0.0; 8]),
+            ]
+        );
+
+        // Samples taken at the same instant have no rate
+        assert!(diskio_since(&last, &last).is_empty());
+    }
+}
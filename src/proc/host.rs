@@ -0,0 +1,381 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    ffi::CStr,
+    fs, io,
+    mem::MaybeUninit,
+    path::Path,
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use super::{get_meminfo, invalid_data, read_boot_time};
+
+/// Fields of the uname syscall.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Uname {
+    pub sysname: String,
+    pub nodename: String,
+    pub release: String,
+    pub version: String,
+    pub machine: String,
+}
+
+pub fn uname() -> io::Result<Uname> {
+    let mut name = MaybeUninit::<libc::utsname>::uninit();
+    // SAFETY: `name` is only read on success, when uname filled it with
+    // NUL terminated strings
+    let name = unsafe {
+        if libc::uname(name.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        name.assume_init()
+    };
+    let field = |field: &[libc::c_char]| {
+        // SAFETY: uname NUL terminates every field
+        unsafe { CStr::from_ptr(field.as_ptr()) }.to_string_lossy().into_owned()
+    };
+
+    Ok(Uname {
+        sysname: field(&name.sysname),
+        nodename: field(&name.nodename),
+        release: field(&name.release),
+        version: field(&name.version),
+        machine: field(&name.machine),
+    })
+}
+
+/// `PRETTY_NAME` of os-release, or its `NAME` and `VERSION`.
+pub fn parse_os_release(data: &str) -> Option<String> {
+    let values: HashMap<&str, &str> = data
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim().trim_matches(|c| c == '"' || c == '\'')))
+        .collect();
+
+    match (values.get("PRETTY_NAME"), values.get("NAME"), values.get("VERSION")) {
+        (Some(pretty), _, _) => Some(pretty.to_string()),
+        (None, Some(name), Some(version)) => Some(format!("{} {}", name, version)),
+        (None, Some(name), None) => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+pub fn read_os_release() -> Option<String> {
+    // /etc/os-release is usually a link to the second one, which is only
+    // there by itself on some distributions
+    ["/etc/os-release", "/usr/lib/os-release"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .and_then(|data| parse_os_release(&data))
+}
+
+pub fn read_uptime() -> io::Result<Duration> {
+    let data = fs::read_to_string("/proc/uptime")?;
+    let seconds: f64 = data
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| invalid_data(String::from("empty /proc/uptime")))?
+        .parse()
+        .map_err(|e| invalid_data(format!("bad /proc/uptime: {}", e)))?;
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// One CPU cache as seen from the first CPU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuCache {
+    pub level: u32,
+    /// `Data`, `Instruction` or `Unified`
+    pub kind: String,
+    /// As reported by the kernel, such as `32K`
+    pub size: String,
+    /// Number of CPUs sharing it
+    pub shared_by: usize,
+}
+
+impl std::fmt::Display for CpuCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suffix = match self.kind.as_str() {
+            "Data" => "d",
+            "Instruction" => "i",
+            _ => "",
+        };
+        write!(f, "L{}{} {}", self.level, suffix, self.size)?;
+        if self.shared_by > 1 {
+            write!(f, " (shared by {} CPUs)", self.shared_by)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuTopology {
+    pub model: Option<String>,
+    pub sockets: usize,
+    /// Physical cores over all sockets
+    pub cores: usize,
+    /// Online CPUs, counting each hardware thread
+    pub threads: usize,
+    pub caches: Vec<CpuCache>,
+}
+
+/// Counts the CPUs of a list such as `0-3,8,10-11`.
+fn cpu_list_len(list: &str) -> usize {
+    list.trim()
+        .split(',')
+        .filter(|range| !range.is_empty())
+        .map(|range| match range.split_once('-') {
+            Some((first, last)) => match (first.parse::<usize>(), last.parse::<usize>()) {
+                (Ok(first), Ok(last)) if last >= first => last - first + 1,
+                _ => 0,
+            },
+            None => 1,
+        })
+        .sum()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+pub fn read_cpu_topology() -> io::Result<CpuTopology> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo")?;
+    // x86 has "model name", some ARM kernels only "Processor" or "Hardware"
+    let model = ["model name", "Processor", "Hardware", "cpu model"]
+        .iter()
+        .find_map(|key| {
+            cpuinfo.lines().find_map(|line| {
+                let (k, v) = line.split_once(':')?;
+                (k.trim() == *key).then(|| v.trim().to_string())
+            })
+        });
+
+    let mut packages = BTreeSet::new();
+    let mut cores = BTreeSet::new();
+    let mut threads = 0;
+    for entry in fs::read_dir("/sys/devices/system/cpu")? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let is_cpu = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("cpu"))
+            .is_some_and(|id| id.parse::<usize>().is_ok());
+        let topology = entry.path().join("topology");
+        // Offline CPUs have no topology
+        if !is_cpu || !topology.exists() {
+            continue;
+        }
+
+        let package = read_trimmed(&topology.join("physical_package_id")).unwrap_or_default();
+        let core = read_trimmed(&topology.join("core_id")).unwrap_or_default();
+        packages.insert(package.clone());
+        cores.insert((package, core));
+        threads += 1;
+    }
+
+    let mut caches = Vec::new();
+    if let Ok(entries) = fs::read_dir("/sys/devices/system/cpu/cpu0/cache") {
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let level = read_trimmed(&dir.join("level")).and_then(|level| level.parse().ok());
+            let (level, kind, size) = match (level, read_trimmed(&dir.join("type")), read_trimmed(&dir.join("size"))) {
+                (Some(level), Some(kind), Some(size)) => (level, kind, size),
+                _ => continue,
+            };
+            caches.push(CpuCache {
+                level,
+                kind,
+                size,
+                shared_by: read_trimmed(&dir.join("shared_cpu_list"))
+                    .map(|list| cpu_list_len(&list))
+                    .unwrap_or(1),
+            });
+        }
+    }
+    caches.sort_by(|a, b| (a.level, &a.kind).cmp(&(b.level, &b.kind)));
+
+    Ok(CpuTopology {
+        model,
+        sockets: packages.len(),
+        cores: cores.len(),
+        threads,
+        caches,
+    })
+}
+
+/// Hypervisor the system runs on, if any, from the DMI vendor strings or,
+/// failing that, the `hypervisor` CPU flag.
+pub fn detect_virtualization() -> Option<String> {
+    const VENDORS: [(&str, &str); 10] = [
+        ("KVM", "KVM"),
+        ("QEMU", "QEMU"),
+        ("VMware", "VMware"),
+        ("VirtualBox", "VirtualBox"),
+        ("innotek", "VirtualBox"),
+        ("Xen", "Xen"),
+        ("Microsoft Corporation", "Hyper-V"),
+        ("Amazon EC2", "Amazon EC2"),
+        ("Google", "Google Compute Engine"),
+        ("Parallels", "Parallels"),
+    ];
+
+    let dmi = ["sys_vendor", "product_name", "bios_vendor"]
+        .iter()
+        .filter_map(|field| read_trimmed(&Path::new("/sys/class/dmi/id").join(field)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if let Some((_, name)) = VENDORS.iter().find(|(vendor, _)| dmi.contains(vendor)) {
+        return Some(name.to_string());
+    }
+
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    let has_flag = cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("flags"))
+        .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"));
+    has_flag.then(|| String::from("Unknown hypervisor"))
+}
+
+/// Container runtime the dashboard runs in, if any.
+pub fn detect_container() -> Option<String> {
+    if Path::new("/.dockerenv").exists() {
+        return Some(String::from("Docker"));
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some(String::from("Podman"));
+    }
+    if let Ok(container) = fs::read_to_string("/run/systemd/container") {
+        return Some(container.trim().to_string());
+    }
+
+    let cgroup = fs::read_to_string("/proc/self/cgroup").ok()?;
+    [("kubepods", "Kubernetes"), ("docker", "Docker"), ("lxc", "LXC"), ("libpod", "Podman")]
+        .iter()
+        .find(|(marker, _)| cgroup.contains(marker))
+        .map(|(_, name)| name.to_string())
+}
+
+/// A login session from utmp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedInUser {
+    pub user: String,
+    /// Terminal, such as `pts/0`
+    pub line: String,
+    /// Remote host for remote logins, empty otherwise
+    pub host: String,
+}
+
+pub fn read_logged_in_users() -> Vec<LoggedInUser> {
+    let field = |field: &[libc::c_char]| {
+        // The fields are only NUL terminated when shorter than the array
+        let bytes: Vec<u8> = field.iter().take_while(|c| **c != 0).map(|c| *c as u8).collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+
+    let mut users = Vec::new();
+    // SAFETY: the utmp functions are not thread safe, but only the
+    // collector thread calls them, and every entry is copied out before
+    // the next call
+    unsafe {
+        libc::setutxent();
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+            let entry = &*entry;
+            if entry.ut_type == libc::USER_PROCESS {
+                users.push(LoggedInUser {
+                    user: field(&entry.ut_user),
+                    line: field(&entry.ut_line),
+                    host: field(&entry.ut_host),
+                });
+            }
+        }
+        libc::endutxent();
+    }
+
+    users
+}
+
+/// What the system information pane shows.
+#[derive(Debug, Clone, Default)]
+pub struct HostInfo {
+    pub uname: Option<Uname>,
+    pub os: Option<String>,
+    pub uptime: Option<Duration>,
+    pub boot_time: Option<DateTime<Utc>>,
+    pub cpu: Option<CpuTopology>,
+    /// In KiB
+    pub mem_total: Option<u64>,
+    pub virtualization: Option<String>,
+    pub container: Option<String>,
+    pub users: Vec<LoggedInUser>,
+}
+
+impl HostInfo {
+    /// Reads everything. `refresh` then only updates what changes while
+    /// the system runs.
+    pub fn read() -> Self {
+        let mut info = HostInfo {
+            uname: uname().ok(),
+            os: read_os_release(),
+            cpu: read_cpu_topology().ok(),
+            mem_total: get_meminfo().ok().map(|mem| mem.mem_total),
+            virtualization: detect_virtualization(),
+            container: detect_container(),
+            ..Default::default()
+        };
+        info.refresh();
+        info
+    }
+
+    pub fn refresh(&mut self) {
+        self.uptime = read_uptime().ok();
+        self.boot_time = read_boot_time().ok().and_then(|btime| Utc.timestamp_opt(btime, 0).single());
+        self.users = read_logged_in_users();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_os_release() {
+        let cases = [
+            (
+                "NAME=\"Ubuntu\"\nVERSION=\"22.04.3 LTS (Jammy Jellyfish)\"\nPRETTY_NAME=\"Ubuntu 22.04.3 LTS\"\n",
+                Some("Ubuntu 22.04.3 LTS"),
+            ),
+            ("PRETTY_NAME='Alpine Linux v3.18'\nNAME=Alpine\n", Some("Alpine Linux v3.18")),
+            ("NAME=Fedora\nVERSION=\"39 (Workstation Edition)\"\n", Some("Fedora 39 (Workstation Edition)")),
+            ("# comment\nNAME=\"Arch Linux\"\n\nID=arch\n", Some("Arch Linux")),
+            ("VERSION=12\nID=debian\n", None),
+            ("", None),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(parse_os_release(data).as_deref(), expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn counts_cpu_lists() {
+        let cases = [
+            ("0\n", 1),
+            ("0-3", 4),
+            ("0-3,8,10-11\n", 7),
+            ("0,2,4,6", 4),
+            // Reversed or broken ranges count for nothing
+            ("3-0", 0),
+            ("0-x,4", 1),
+            ("", 0),
+            ("\n", 0),
+        ];
+
+        for (list, expected) in cases {
+            assert_eq!(cpu_list_len(list), expected, "{:?}", list);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::{fs, io};
+
+use super::invalid_data;
+
+/// Load averages of /proc/loadavg, along with the run queue of /proc/stat.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemLoad {
+    pub load1: f64,
+    pub load5: f64,
+    pub load15: f64,
+    /// Tasks runnable right now
+    pub procs_running: u64,
+    /// Tasks waiting on I/O
+    pub procs_blocked: u64,
+    /// Online CPUs. A load above this means tasks are waiting for a CPU.
+    pub cpus: usize,
+}
+
+/// Returns the 1, 5 and 15 minute load averages.
+pub fn parse_loadavg(data: &str) -> io::Result<(f64, f64, f64)> {
+    let mut fields = data.split_whitespace().map(|f| {
+        f.parse::<f64>()
+            .map_err(|e| invalid_data(format!("bad /proc/loadavg value {:?}: {}", f, e)))
+    });
+    let mut next = || {
+        fields
+            .next()
+            .unwrap_or_else(|| Err(invalid_data(String::from("short /proc/loadavg"))))
+    };
+
+    Ok((next()?, next()?, next()?))
+}
+
+/// Fills in the run queue and CPU count of `load` from /proc/stat.
+pub fn parse_stat_run_queue(data: &str, load: &mut SystemLoad) -> io::Result<()> {
+    let mut found = false;
+
+    for line in data.lines() {
+        let (name, value) = match line.split_once(' ') {
+            Some(field) => field,
+            None => continue,
+        };
+        let count = || {
+            value
+                .trim()
+                .parse()
+                .map_err(|e| invalid_data(format!("bad /proc/stat {} {:?}: {}", name, value, e)))
+        };
+        match name {
+            "procs_running" => {
+                load.procs_running = count()?;
+                found = true;
+            }
+            "procs_blocked" => load.procs_blocked = count()?,
+            // The aggregate line is just "cpu"
+            _ if name.len() > 3 && name.starts_with("cpu") => load.cpus += 1,
+            _ => {}
+        }
+    }
+
+    if !found {
+        return Err(invalid_data(String::from("no procs_running in /proc/stat")));
+    }
+
+    Ok(())
+}
+
+pub fn read_system_load() -> io::Result<SystemLoad> {
+    let (load1, load5, load15) = parse_loadavg(&fs::read_to_string("/proc/loadavg")?)?;
+    let mut load = SystemLoad {
+        load1,
+        load5,
+        load15,
+        ..Default::default()
+    };
+    parse_stat_run_queue(&fs::read_to_string("/proc/stat")?, &mut load)?;
+    Ok(load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_loadavg() {
+        let cases = [
+            ("0.52 0.58 0.59 2/1234 56789\n", Some((0.52, 0.58, 0.59))),
+            ("12.00 8.50 4.25 17/2000 1", Some((12.0, 8.5, 4.25))),
+            ("0.00 0.00 0.00", Some((0.0, 0.0, 0.0))),
+            ("0.52 0.58", None),
+            ("0.52 high 0.59 2/1234 56789", None),
+            ("", None),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(parse_loadavg(data).ok(), expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn parses_stat_run_queue() {
+        let data = "cpu  10 1 5 100 2 0 3 0 0 0\n\
+                    cpu0 6 1 3 50 1 0 2 0 0 0\n\
+                    cpu1 4 0 2 50 1 0 1 0 0 0\n\
+                    ctxt 6789\n\
+                    procs_running 3\n\
+                    procs_blocked 1\n";
+
+        let mut load = SystemLoad::default();
+        parse_stat_run_queue(data, &mut load).unwrap();
+        assert_eq!((load.procs_running, load.procs_blocked, load.cpus), (3, 1, 2));
+
+        let mut load = SystemLoad::default();
+        assert!(parse_stat_run_queue("cpu 1 2 3 4\nprocs_blocked 1\n", &mut load).is_err());
+        assert!(parse_stat_run_queue("procs_running many\n", &mut load).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use std::{fs, io, path::Path, time::Instant};
+
+use super::invalid_data;
+
+/// Counters of one interface in /proc/net/dev.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetDevStats {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_drops: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_drops: u64,
+}
+
+pub fn parse_net_dev(data: &str) -> io::Result<Vec<NetDevStats>> {
+    // The first two lines are the column headers
+    data.lines()
+        .skip(2)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (name, counters) = line
+                .split_once(':')
+                .ok_or_else(|| invalid_data(format!("bad /proc/net/dev line {:?}", line)))?;
+            let counters = counters
+                .split_whitespace()
+                .map(|f| {
+                    f.parse()
+                        .map_err(|e| invalid_data(format!("bad /proc/net/dev value {:?}: {}", f, e)))
+                })
+                .collect::<io::Result<Vec<u64>>>()?;
+            if counters.len() < 12 {
+                return Err(invalid_data(format!("bad /proc/net/dev line {:?}", line)));
+            }
+
+            // 8 receive columns, then the transmit ones
+            Ok(NetDevStats {
+                name: name.trim().to_string(),
+                rx_bytes: counters[0],
+                rx_packets: counters[1],
+                rx_errors: counters[2],
+                rx_drops: counters[3],
+                tx_bytes: counters[8],
+                tx_packets: counters[9],
+                tx_errors: counters[10],
+                tx_drops: counters[11],
+            })
+        })
+        .collect()
+}
+
+/// A sample of /proc/net/dev and when it was taken.
+#[derive(Debug, Clone)]
+pub struct NetDevSample {
+    pub time: Instant,
+    pub interfaces: Vec<NetDevStats>,
+}
+
+impl Default for NetDevSample {
+    fn default() -> Self {
+        Self {
+            time: Instant::now(),
+            interfaces: Vec::new(),
+        }
+    }
+}
+
+pub fn read_net_dev() -> io::Result<NetDevSample> {
+    Ok(NetDevSample {
+        time: Instant::now(),
+        interfaces: parse_net_dev(&fs::read_to_string("/proc/net/dev")?)?,
+    })
+}
+
+/// Attributes of an interface from /sys/class/net. Each is `None` when
+/// the interface does not report it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceInfo {
+    pub mtu: Option<u32>,
+    pub operstate: Option<String>,
+    /// Link speed in Mbit/s
+    pub speed: Option<u32>,
+    pub address: Option<String>,
+}
+
+pub fn read_interface_info(name: &str) -> InterfaceInfo {
+    let dir = Path::new("/sys/class/net").join(name);
+    let read = |attribute: &str| {
+        fs::read_to_string(dir.join(attribute))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    InterfaceInfo {
+        mtu: read("mtu").and_then(|mtu| mtu.parse().ok()),
+        operstate: read("operstate"),
+        // Reading it fails on virtual interfaces, and it is -1 when the
+        // link is down
+        speed: read("speed").and_then(|speed| speed.parse().ok()),
+        address: read("address"),
+    }
+}
+
+/// Traffic of a network interface between two samples of /proc/net/dev,
+/// per second.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetIo {
+    pub name: String,
+    pub info: InterfaceInfo,
+    pub rx_bytes: f64,
+    pub tx_bytes: f64,
+    pub rx_packets: f64,
+    pub tx_packets: f64,
+    pub rx_errors: f64,
+    pub tx_errors: f64,
+    pub rx_drops: f64,
+    pub tx_drops: f64,
+}
+
+/// Per-second traffic between two samples of /proc/net/dev. Interfaces
+/// are matched by name; those that were not in `last` are left out.
+fn netio_since(sample: &NetDevSample, last: &NetDevSample) -> Vec<NetIo> {
+    let elapsed = sample.time.duration_since(last.time).as_secs_f64();
+
+    if elapsed > 0.0 {
+        sample
+            .interfaces
+            .iter()
+            .filter_map(|interface| {
+                let prev = last.interfaces.iter().find(|prev| prev.name == interface.name)?;
+                // Counters reset when a driver is reloaded, and some drivers
+                // keep 32-bit counters that wrap around. Either way the
+                // counter reads zero for that interval.
+                let rate = |now: u64, prev: u64| now.saturating_sub(prev) as f64 / elapsed;
+
+                Some(NetIo {
+                    name: interface.name.clone(),
+                    info: read_interface_info(&interface.name),
+                    rx_bytes: rate(interface.rx_bytes, prev.rx_bytes),
+                    tx_bytes: rate(interface.tx_bytes, prev.tx_bytes),
+                    rx_packets: rate(interface.rx_packets, prev.rx_packets),
+                    tx_packets: rate(interface.tx_packets, prev.tx_packets),
+                    rx_errors: rate(interface.rx_errors, prev.rx_errors),
+                    tx_errors: rate(interface.tx_errors, prev.tx_errors),
+                    rx_drops: rate(interface.rx_drops, prev.rx_drops),
+                    tx_drops: rate(interface.tx_drops, prev.tx_drops),
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Samples /proc/net/dev and returns the per-second traffic since the
+/// sample in `last`, which is then replaced by the new one. Interfaces
+/// that were not in `last` are left out until the next call.
+pub fn get_netio(last: &mut NetDevSample) -> io::Result<Vec<NetIo>> {
+    let sample = read_net_dev()?;
+    let interfaces = netio_since(&sample, last);
+    *last = sample;
+
+    Ok(interfaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const HEADER: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+";
+
+    #[test]
+    fn parses_net_dev() {
+        let cases: [(&str, Option<Vec<NetDevStats>>); 5] = [
+            (
+                "    lo: 93313251   11313    0    0    0     0          0         0 93313251   11313    0    0    0     0       0          0
+  eth0: 70724266    5694    1    2    0     0          0         7   458367    5497    3    4    0     0       0          0
+",
+                Some(vec![
+                    NetDevStats {
+                        name: String::from("lo"),
+                        rx_bytes: 93313251,
+                        rx_packets: 11313,
+                        tx_bytes: 93313251,
+                        tx_packets: 11313,
+                        ..NetDevStats::default()
+                    },
+                    NetDevStats {
+                        name: String::from("eth0"),
+                        rx_bytes: 70724266,
+                        rx_packets: 5694,
+                        rx_errors: 1,
+                        rx_drops: 2,
+                        tx_bytes: 458367,
+                        tx_packets: 5497,
+                        tx_errors: 3,
+                        tx_drops: 4,
+                    },
+                ]),
+            ),
+            // Busy interfaces leave no space after the colon
+            (
+                "eth1:123 4 0 0 0 0 0 0 567 8 0 0 0 0 0 0\n",
+                Some(vec![NetDevStats {
+                    name: String::from("eth1"),
+                    rx_bytes: 123,
+                    rx_packets: 4,
+                    tx_bytes: 567,
+                    tx_packets: 8,
+                    ..NetDevStats::default()
+                }]),
+            ),
+            ("", Some(vec![])),
+            ("eth0 123 4 0 0 0 0 0 0 567 8 0 0 0 0 0 0\n", None),
+            ("eth0: 123 4 0 0 0 0 0 0 567 8 0\n", None),
+        ];
+
+        for (lines, expected) in cases {
+            let data = format!("{}{}", HEADER, lines);
+            assert_eq!(parse_net_dev(&data).ok(), expected, "{:?}", lines);
+        }
+    }
+
+    #[test]
+    fn computes_netio_between_samples() {
+        let stats = |name: &str, rx_bytes, tx_bytes, rx_packets, tx_packets| NetDevStats {
+            name: name.to_string(),
+            rx_bytes,
+            tx_bytes,
+            rx_packets,
+            tx_packets,
+            ..NetDevStats::default()
+        };
+        let last = NetDevSample {
+            time: Instant::now(),
+            interfaces: vec![
+                stats("eth0", 1_000_000, 50_000, 1000, 500),
+                stats("wlan0", u32::MAX as u64 - 999, 2000, 100, 10),
+                // Unplugged before the next sample
+                stats("usb0", 10, 10, 1, 1),
+            ],
+        };
+        let sample = NetDevSample {
+            time: last.time + Duration::from_secs(2),
+            interfaces: vec![
+                stats("eth0", 3_000_000, 60_000, 3000, 600),
+                // A 32-bit counter that wrapped around
+                stats("wlan0", 1000, 4000, 120, 14),
+                // Plugged in since the last sample
+                stats("usb1", 10, 10, 1, 1),
+            ],
+        };
+
+        let interfaces = netio_since(&sample, &last);
+        let summary: Vec<(&str, [f64; 4])> = interfaces
+            .iter()
+            .map(|i| (i.name.as_str(), [i.rx_bytes, i.tx_bytes, i.rx_packets, i.tx_packets]))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("eth0", [1_000_000.0, 5000.0, 1000.0, 50.0]),
+                // Dropped rather than shown as a huge spike
+                ("wlan0", [0.0, 1000.0, 10.0, 2.0]),
+            ]
+        );
+
+        assert!(netio_since(&last, &last).is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{invalid_data, read_mounts};
+
+/// Resources the kernel reports pressure stall information for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureResource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl PressureResource {
+    pub const ALL: [PressureResource; 3] = [PressureResource::Cpu, PressureResource::Memory, PressureResource::Io];
+
+    /// Name of the file in /proc/pressure, and of the `.pressure` file of
+    /// a cgroup.
+    fn file_name(&self) -> &'static str {
+        match self {
+            PressureResource::Cpu => "cpu",
+            PressureResource::Memory => "memory",
+            PressureResource::Io => "io",
+        }
+    }
+}
+
+impl std::fmt::Display for PressureResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PressureResource::Cpu => write!(f, "CPU"),
+            PressureResource::Memory => write!(f, "Memory"),
+            PressureResource::Io => write!(f, "I/O"),
+        }
+    }
+}
+
+/// One line of a pressure file. The averages are percentages of wall time
+/// over the last 10, 60 and 300 seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Total stall time in microseconds
+    pub total: u64,
+}
+
+/// Pressure of one resource. `some` is the share of time at least one task
+/// was stalled on it, `full` the share of time all non-idle tasks were.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourcePressure {
+    pub some: PressureLine,
+    /// Zero for the system-wide CPU pressure, which kernels before 5.13 do
+    /// not report at all
+    pub full: PressureLine,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    pub cpu: ResourcePressure,
+    pub memory: ResourcePressure,
+    pub io: ResourcePressure,
+}
+
+impl Pressure {
+    pub fn get(&self, resource: PressureResource) -> &ResourcePressure {
+        match resource {
+            PressureResource::Cpu => &self.cpu,
+            PressureResource::Memory => &self.memory,
+            PressureResource::Io => &self.io,
+        }
+    }
+}
+
+pub fn parse_pressure(data: &str) -> io::Result<ResourcePressure> {
+    let mut pressure = ResourcePressure::default();
+    let mut found_some = false;
+
+    for line in data.lines() {
+        let mut fields = line.split_whitespace();
+        let target = match fields.next() {
+            Some("some") => {
+                found_some = true;
+                &mut pressure.some
+            }
+            Some("full") => &mut pressure.full,
+            _ => continue,
+        };
+
+        for field in fields {
+            let bad = || invalid_data(format!("bad pressure field {:?}", field));
+            let (key, value) = field.split_once('=').ok_or_else(bad)?;
+            match key {
+                "avg10" => target.avg10 = value.parse().map_err(|_| bad())?,
+                "avg60" => target.avg60 = value.parse().map_err(|_| bad())?,
+                "avg300" => target.avg300 = value.parse().map_err(|_| bad())?,
+                "total" => target.total = value.parse().map_err(|_| bad())?,
+                _ => {}
+            }
+        }
+    }
+
+    if !found_some {
+        return Err(invalid_data(String::from("no some line in pressure file")));
+    }
+
+    Ok(pressure)
+}
+
+fn read_pressure_files(path: impl Fn(PressureResource) -> PathBuf) -> io::Result<Pressure> {
+    let read = |resource| parse_pressure(&fs::read_to_string(path(resource))?);
+
+    Ok(Pressure {
+        cpu: read(PressureResource::Cpu)?,
+        memory: read(PressureResource::Memory)?,
+        io: read(PressureResource::Io)?,
+    })
+}
+
+/// System-wide pressure from /proc/pressure. Fails on kernels built
+/// without PSI or booted with `psi=0`.
+pub fn read_pressure() -> io::Result<Pressure> {
+    read_pressure_files(|resource| Path::new("/proc/pressure").join(resource.file_name()))
+}
+
+/// Pressure of a cgroup, given by its path below the cgroup2 mount such as
+/// `system.slice/sshd.service`.
+pub fn read_cgroup_pressure(cgroup: &str) -> io::Result<Pressure> {
+    let relative = Path::new(cgroup.trim_start_matches('/'));
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("bad cgroup path {:?}", cgroup),
+        ));
+    }
+
+    let root = read_mounts()?
+        .into_iter()
+        .find(|mount| mount.fs_type == "cgroup2")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cgroup2 filesystem is mounted"))?;
+    let dir = Path::new(&root.mount_point).join(relative);
+    if !dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no cgroup {:?}", cgroup),
+        ));
+    }
+
+    read_pressure_files(|resource| dir.join(format!("{}.pressure", resource.file_name())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(avg10: f64, avg60: f64, avg300: f64, total: u64) -> PressureLine {
+        PressureLine { avg10, avg60, avg300, total }
+    }
+
+    #[test]
+    fn parses_pressure() {
+        let cases = [
+            (
+                "some avg10=1.50 avg60=0.75 avg300=0.10 total=123456\n\
+                 full avg10=0.25 avg60=0.05 avg300=0.00 total=7890\n",
+                Some(ResourcePressure {
+                    some: line(1.5, 0.75, 0.1, 123456),
+                    full: line(0.25, 0.05, 0.0, 7890),
+                }),
+            ),
+            // The system-wide CPU pressure has no full line before 5.13
+            (
+                "some avg10=0.00 avg60=0.00 avg300=0.00 total=42\n",
+                Some(ResourcePressure {
+                    some: line(0.0, 0.0, 0.0, 42),
+                    full: PressureLine::default(),
+                }),
+            ),
+            // Unknown keys are left for newer kernels
+            (
+                "some avg10=2.00 avg60=1.00 avg300=0.50 total=9 avg1=3.00\n",
+                Some(ResourcePressure {
+                    some: line(2.0, 1.0, 0.5, 9),
+                    full: PressureLine::default(),
+                }),
+            ),
+            ("full avg10=0.25 avg60=0.05 avg300=0.00 total=7890\n", None),
+            ("some avg10=high avg60=0.05 avg300=0.00 total=1\n", None),
+            ("some avg10 avg60=0.05 avg300=0.00 total=1\n", None),
+            ("some avg10=0.00 avg60=0.00 avg300=0.00 total=-1\n", None),
+            ("", None),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(parse_pressure(data).ok(), expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn rejects_cgroup_paths_outside_the_hierarchy() {
+        for cgroup in ["../etc", "system.slice/../..", "./system.slice"] {
+            let error = read_cgroup_pressure(cgroup).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{:?}", cgroup);
+        }
+    }
+}
@@ -0,0 +1,485 @@
+use std::{collections::HashMap, fs, io};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use super::{invalid_data, read_cpu_stat, CpuTimes};
+
+/// Fields of /proc/[pid]/stat. Times are in clock ticks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessStat {
+    pub pid: i32,
+    pub name: String,
+    pub state: char,
+    pub ppid: i32,
+    pub utime: u64,
+    pub stime: u64,
+    pub priority: i64,
+    pub nice: i64,
+    pub num_threads: u64,
+    /// Ticks between boot and the process start
+    pub start_time: u64,
+    /// Virtual memory size in bytes
+    pub vsize: u64,
+    /// Resident set size in pages
+    pub rss: u64,
+}
+
+pub fn parse_process_stat(data: &str) -> io::Result<ProcessStat> {
+    // The name is wrapped in parentheses and may itself contain spaces and
+    // parentheses, so everything up to the last ')' belongs to it.
+    let (open, close) = match (data.find('('), data.rfind(')')) {
+        (Some(open), Some(close)) if open < close => (open, close),
+        _ => return Err(invalid_data(format!("bad /proc/[pid]/stat: {:?}", data))),
+    };
+
+    let pid = data[..open]
+        .trim()
+        .parse()
+        .map_err(|e| invalid_data(format!("bad pid in /proc/[pid]/stat: {}", e)))?;
+    let name = data[open + 1..close].to_string();
+    // Fields after the name, starting with field 3 (state)
+    let fields: Vec<&str> = data[close + 1..].split_whitespace().collect();
+
+    let field = |n: usize| -> io::Result<&str> {
+        fields
+            .get(n - 3)
+            .copied()
+            .ok_or_else(|| invalid_data(format!("missing field {} in /proc/{}/stat", n, pid)))
+    };
+    let number = |n: usize| -> io::Result<i64> {
+        field(n)?
+            .parse()
+            .map_err(|e| invalid_data(format!("bad field {} in /proc/{}/stat: {}", n, pid, e)))
+    };
+    let unsigned = |n: usize| -> io::Result<u64> {
+        field(n)?
+            .parse()
+            .map_err(|e| invalid_data(format!("bad field {} in /proc/{}/stat: {}", n, pid, e)))
+    };
+
+    Ok(ProcessStat {
+        pid,
+        name,
+        state: field(3)?.chars().next().unwrap_or('?'),
+        ppid: number(4)? as i32,
+        utime: unsigned(14)?,
+        stime: unsigned(15)?,
+        priority: number(18)?,
+        nice: number(19)?,
+        num_threads: unsigned(20)?,
+        start_time: unsigned(22)?,
+        vsize: unsigned(23)?,
+        rss: unsigned(24)?,
+    })
+}
+
+/// Values of the `Key:\tvalue` lines of /proc/[pid]/status.
+pub fn parse_process_status(data: &str) -> HashMap<&str, &str> {
+    data.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key, value.trim()))
+        .collect()
+}
+
+/// A process as shown in the process table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: i32,
+    pub ppid: i32,
+    /// Executable name, truncated by the kernel to 15 characters
+    pub name: String,
+    /// Arguments joined by spaces, empty for kernel threads
+    pub cmdline: String,
+    pub state: char,
+    pub uid: u32,
+    pub user: String,
+    pub nice: i64,
+    pub num_threads: u64,
+    /// Resident set size in KiB
+    pub rss: u64,
+    pub start_time: DateTime<Utc>,
+    /// `ProcessStat::start_time`, which tells the process apart from a
+    /// later one given the same PID
+    pub start_ticks: u64,
+    /// Share of one core used since the previous sample, so it can go over
+    /// 100% for multi-threaded processes
+    pub cpu_percent: f64,
+    /// Share of the total memory in resident pages
+    pub mem_percent: f64,
+}
+
+impl ProcessInfo {
+    /// The command line, or the name in brackets for kernel threads, like ps.
+    pub fn command(&self) -> String {
+        if self.cmdline.is_empty() {
+            format!("[{}]", self.name)
+        } else {
+            self.cmdline.clone()
+        }
+    }
+}
+
+/// Reads the user names from /etc/passwd.
+pub fn read_users() -> io::Result<HashMap<u32, String>> {
+    let data = fs::read_to_string("/etc/passwd")?;
+
+    Ok(data
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect())
+}
+
+/// Reads the system boot time, as seconds since the epoch.
+pub fn read_boot_time() -> io::Result<i64> {
+    fs::read_to_string("/proc/stat")?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .ok_or_else(|| invalid_data(String::from("no btime in /proc/stat")))?
+        .trim()
+        .parse()
+        .map_err(|e| invalid_data(format!("bad btime in /proc/stat: {}", e)))
+}
+
+pub fn clock_ticks() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+/// Keeps the CPU times of the previous sample so that the usage of each
+/// process can be computed from the difference, like `get_cpuinfo` does for
+/// the cores.
+pub struct ProcessSampler {
+    last_total: CpuTimes,
+    last_cpu_times: HashMap<i32, u64>,
+    users: HashMap<u32, String>,
+    clock_ticks: u64,
+    boot_time: i64,
+}
+
+impl ProcessSampler {
+    pub fn new() -> Self {
+        Self {
+            last_total: CpuTimes::default(),
+            last_cpu_times: HashMap::new(),
+            users: read_users().unwrap_or_default(),
+            clock_ticks: clock_ticks(),
+            boot_time: read_boot_time().unwrap_or_default(),
+        }
+    }
+
+    pub fn clock_ticks(&self) -> u64 {
+        self.clock_ticks
+    }
+
+    pub fn sample(&mut self) -> io::Result<Vec<ProcessInfo>> {
+        let cpu = read_cpu_stat()?;
+        let mem_total = super::get_meminfo()?.mem_total;
+
+        // Ticks that went by on each core since the last sample
+        let elapsed = cpu.total.total_time().saturating_sub(self.last_total.total_time()) as f64
+            / cpu.cores.len().max(1) as f64;
+        self.last_total = cpu.total;
+
+        let mut cpu_times = HashMap::new();
+        let mut processes = vec![];
+
+        for entry in fs::read_dir("/proc")? {
+            let pid: i32 = match entry?.file_name().to_str().and_then(|name| name.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+
+            // The process may exit while it is being read
+            let process = match self.read_process(pid, mem_total) {
+                Ok(process) => process,
+                Err(_) => continue,
+            };
+            let (mut process, cpu_time) = process;
+
+            let last_cpu_time = self.last_cpu_times.get(&pid).copied().unwrap_or_default();
+            if elapsed > 0.0 {
+                process.cpu_percent = cpu_time.saturating_sub(last_cpu_time) as f64 / elapsed * 100.0;
+            }
+            cpu_times.insert(pid, cpu_time);
+            processes.push(process);
+        }
+
+        self.last_cpu_times = cpu_times;
+
+        Ok(processes)
+    }
+
+    fn read_process(&self, pid: i32, mem_total: u64) -> io::Result<(ProcessInfo, u64)> {
+        let stat = parse_process_stat(&fs::read_to_string(format!("/proc/{}/stat", pid))?)?;
+        let status_data = fs::read_to_string(format!("/proc/{}/status", pid))?;
+        let status = parse_process_status(&status_data);
+        let cmdline = fs::read(format!("/proc/{}/cmdline", pid))?;
+
+        // Real UID is the first of the four
+        let uid = status
+            .get("Uid")
+            .and_then(|uids| uids.split_whitespace().next())
+            .and_then(|uid| uid.parse().ok())
+            .unwrap_or_default();
+        let rss = status
+            .get("VmRSS")
+            .and_then(|rss| rss.split_whitespace().next())
+            .and_then(|rss| rss.parse().ok())
+            .unwrap_or_default();
+
+        let cmdline = String::from_utf8_lossy(&cmdline)
+            .split('\0')
+            .filter(|arg| !arg.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let start_millis =
+            self.boot_time * 1000 + (stat.start_time * 1000 / self.clock_ticks) as i64;
+
+        let process = ProcessInfo {
+            pid,
+            ppid: stat.ppid,
+            user: self
+                .users
+                .get(&uid)
+                .cloned()
+                .unwrap_or_else(|| uid.to_string()),
+            uid,
+            cmdline,
+            state: stat.state,
+            nice: stat.nice,
+            num_threads: stat.num_threads,
+            rss,
+            start_time: Utc.timestamp_millis_opt(start_millis).single().unwrap_or_default(),
+            start_ticks: stat.start_time,
+            cpu_percent: 0.0,
+            mem_percent: if mem_total > 0 {
+                rss as f64 / mem_total as f64 * 100.0
+            } else {
+                0.0
+            },
+            name: stat.name,
+        };
+
+        Ok((process, stat.utime + stat.stime))
+    }
+}
+
+impl Default for ProcessSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A signal that can be sent to a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal(pub i32);
+
+impl Signal {
+    pub const TERM: Signal = Signal(libc::SIGTERM);
+    pub const KILL: Signal = Signal(libc::SIGKILL);
+    pub const STOP: Signal = Signal(libc::SIGSTOP);
+    pub const CONT: Signal = Signal(libc::SIGCONT);
+
+    /// The standard signals, in numeric order.
+    pub const ALL: [Signal; 20] = [
+        Signal(libc::SIGHUP),
+        Signal(libc::SIGINT),
+        Signal(libc::SIGQUIT),
+        Signal(libc::SIGILL),
+        Signal(libc::SIGTRAP),
+        Signal(libc::SIGABRT),
+        Signal(libc::SIGBUS),
+        Signal(libc::SIGFPE),
+        Signal::KILL,
+        Signal(libc::SIGUSR1),
+        Signal(libc::SIGSEGV),
+        Signal(libc::SIGUSR2),
+        Signal(libc::SIGPIPE),
+        Signal(libc::SIGALRM),
+        Signal::TERM,
+        Signal::CONT,
+        Signal::STOP,
+        Signal(libc::SIGTSTP),
+        Signal(libc::SIGWINCH),
+        Signal(libc::SIGPWR),
+    ];
+
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self.0 {
+            libc::SIGHUP => "SIGHUP",
+            libc::SIGINT => "SIGINT",
+            libc::SIGQUIT => "SIGQUIT",
+            libc::SIGILL => "SIGILL",
+            libc::SIGTRAP => "SIGTRAP",
+            libc::SIGABRT => "SIGABRT",
+            libc::SIGBUS => "SIGBUS",
+            libc::SIGFPE => "SIGFPE",
+            libc::SIGKILL => "SIGKILL",
+            libc::SIGUSR1 => "SIGUSR1",
+            libc::SIGSEGV => "SIGSEGV",
+            libc::SIGUSR2 => "SIGUSR2",
+            libc::SIGPIPE => "SIGPIPE",
+            libc::SIGALRM => "SIGALRM",
+            libc::SIGTERM => "SIGTERM",
+            libc::SIGCONT => "SIGCONT",
+            libc::SIGSTOP => "SIGSTOP",
+            libc::SIGTSTP => "SIGTSTP",
+            libc::SIGWINCH => "SIGWINCH",
+            libc::SIGPWR => "SIGPWR",
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{} ({})", name, self.0),
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+/// `ProcessStat::start_time` of a running process.
+pub fn read_start_ticks(pid: i32) -> io::Result<u64> {
+    Ok(parse_process_stat(&fs::read_to_string(format!("/proc/{}/stat", pid))?)?.start_time)
+}
+
+pub fn send_signal(pid: i32, signal: Signal) -> io::Result<()> {
+    // SAFETY: kill has no memory safety preconditions
+    if unsafe { libc::kill(pid, signal.0) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Sets the nice value of a process, from -20 (highest priority) to 19.
+pub fn set_nice(pid: i32, nice: i32) -> io::Result<()> {
+    // SAFETY: setpriority has no memory safety preconditions
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// I/O scheduling class and level, as set by `ionice`. Lower levels get a
+/// bigger share of the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoPriority {
+    RealTime(u8),
+    BestEffort(u8),
+    Idle,
+}
+
+impl IoPriority {
+    pub fn all() -> Vec<IoPriority> {
+        (0..8)
+            .map(IoPriority::RealTime)
+            .chain((0..8).map(IoPriority::BestEffort))
+            .chain(std::iter::once(IoPriority::Idle))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoPriority::RealTime(level) => write!(f, "real-time {}", level),
+            IoPriority::BestEffort(level) => write!(f, "best-effort {}", level),
+            IoPriority::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+pub fn set_io_priority(pid: i32, priority: IoPriority) -> io::Result<()> {
+    // From linux/ioprio.h
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    let value = match priority {
+        IoPriority::RealTime(level) => 1 << IOPRIO_CLASS_SHIFT | level as libc::c_int,
+        IoPriority::BestEffort(level) => 2 << IOPRIO_CLASS_SHIFT | level as libc::c_int,
+        IoPriority::Idle => 3 << IOPRIO_CLASS_SHIFT,
+    };
+
+    // SAFETY: ioprio_set takes integers only
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, pid, value) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// /proc/[pid]/stat of a process named `name`, with the fields after it.
+    fn stat_line(pid: i32, name: &str) -> String {
+        format!(
+            "{} ({}) S 1 1234 1234 0 -1 4194560 1500 0 0 0 250 75 0 0 20 0 3 0 98765 \
+             123456789 4321 18446744073709551615 1 1 0 0 0 0 0 4096 0 0 0 0 17 2 0 0 0 0 0",
+            pid, name
+        )
+    }
+
+    #[test]
+    fn parses_process_stat() {
+        let cases = [
+            (stat_line(42, "bash"), "bash"),
+            (stat_line(42, "Web Content"), "Web Content"),
+            // The name ends at the last ')', whatever it contains
+            (stat_line(42, "a) b (c"), "a) b (c"),
+            (stat_line(42, ")) S 9 9"), ")) S 9 9"),
+            (stat_line(42, ""), ""),
+        ];
+
+        for (data, name) in cases {
+            let stat = parse_process_stat(&data).unwrap();
+            assert_eq!(
+                stat,
+                ProcessStat {
+                    pid: 42,
+                    name: name.to_string(),
+                    state: 'S',
+                    ppid: 1,
+                    utime: 250,
+                    stime: 75,
+                    priority: 20,
+                    nice: 0,
+                    num_threads: 3,
+                    start_time: 98765,
+                    vsize: 123456789,
+                    rss: 4321,
+                },
+                "{:?}",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_process_stat() {
+        let cases = [
+            String::new(),
+            String::from("42 bash S 1"),
+            String::from("42 (bash S 1"),
+            String::from("x (bash) S 1"),
+            // Cut off before the RSS
+            String::from("42 (bash) S 1 1234 1234 0 -1 4194560 1500 0 0 0 250 75 0 0 20 0 3 0 98765"),
+            stat_line(42, "bash").replace(" 250 ", " lots "),
+        ];
+
+        for data in cases {
+            assert!(parse_process_stat(&data).is_err(), "{:?}", data);
+        }
+    }
+}
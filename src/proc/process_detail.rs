@@ -0,0 +1,253 @@
+use std::{fs, io, path::PathBuf};
+
+use super::{invalid_data, parse_process_stat};
+
+/// One line of /proc/[pid]/limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessLimit {
+    pub name: String,
+    pub soft: String,
+    pub hard: String,
+    /// Empty for limits without a unit, like the number of processes
+    pub units: String,
+}
+
+/// A thread from /proc/[pid]/task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadInfo {
+    pub tid: i32,
+    pub name: String,
+    pub state: char,
+    /// User plus system time, in clock ticks
+    pub cpu_time: u64,
+}
+
+/// Everything shown in the detail pane of a process. Apart from the stat
+/// and status files, reading another user's process usually needs root,
+/// so each part keeps its own error.
+#[derive(Debug)]
+pub struct ProcessDetail {
+    pub pid: i32,
+    /// `Key: value` lines of /proc/[pid]/status, in file order
+    pub status: Vec<(String, String)>,
+    pub cmdline: Vec<String>,
+    pub cwd: io::Result<PathBuf>,
+    pub exe: io::Result<PathBuf>,
+    pub environ: io::Result<Vec<String>>,
+    pub limits: io::Result<Vec<ProcessLimit>>,
+    pub threads: io::Result<Vec<ThreadInfo>>,
+    /// Clock ticks per second, to turn the CPU time of the threads into
+    /// seconds
+    pub clock_ticks: u64,
+    /// File descriptor number and what it points to
+    pub fds: io::Result<Vec<(u32, String)>>,
+    /// Fields of /proc/[pid]/smaps_rollup, in KiB
+    pub memory: io::Result<Vec<(String, u64)>>,
+}
+
+pub fn parse_limits(data: &str) -> io::Result<Vec<ProcessLimit>> {
+    let mut lines = data.lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| invalid_data(String::from("empty /proc/[pid]/limits")))?;
+
+    // Columns are padded to fixed widths, and the limit names have spaces,
+    // so the header tells where each column starts
+    let column = |name: &str| {
+        header
+            .find(name)
+            .ok_or_else(|| invalid_data(format!("no {:?} column in /proc/[pid]/limits", name)))
+    };
+    let soft = column("Soft Limit")?;
+    let hard = column("Hard Limit")?;
+    let units = column("Units")?;
+
+    let field = |line: &str, start: usize, end: usize| {
+        line.get(start..end.min(line.len())).unwrap_or_default().trim().to_string()
+    };
+
+    Ok(lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| ProcessLimit {
+            name: field(line, 0, soft),
+            soft: field(line, soft, hard),
+            hard: field(line, hard, units),
+            units: field(line, units, line.len()),
+        })
+        .collect())
+}
+
+pub fn parse_smaps_rollup(data: &str) -> Vec<(String, u64)> {
+    // The first line is the address range of the rollup
+    data.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.split_whitespace().next()?.parse().ok()?;
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+fn read_threads(pid: i32) -> io::Result<Vec<ThreadInfo>> {
+    let mut threads = vec![];
+
+    for entry in fs::read_dir(format!("/proc/{}/task", pid))? {
+        let path = entry?.path();
+        // Threads can exit while they are being listed, and one that cannot
+        // be read is left out rather than failing the whole list
+        let stat = match fs::read_to_string(path.join("stat")).and_then(|stat| parse_process_stat(&stat)) {
+            Ok(stat) => stat,
+            Err(_) => continue,
+        };
+
+        threads.push(ThreadInfo {
+            tid: stat.pid,
+            name: stat.name,
+            state: stat.state,
+            cpu_time: stat.utime + stat.stime,
+        });
+    }
+
+    threads.sort_by_key(|thread| thread.tid);
+    Ok(threads)
+}
+
+fn read_fds(pid: i32) -> io::Result<Vec<(u32, String)>> {
+    let mut fds = vec![];
+
+    for entry in fs::read_dir(format!("/proc/{}/fd", pid))? {
+        let entry = entry?;
+        let fd = match entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+            Some(fd) => fd,
+            None => continue,
+        };
+        let target = match fs::read_link(entry.path()) {
+            Ok(target) => target.to_string_lossy().into_owned(),
+            // Closed while the list was being read
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        fds.push((fd, target));
+    }
+
+    fds.sort();
+    Ok(fds)
+}
+
+fn split_nul(data: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(data)
+        .split('\0')
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Reads what the detail pane shows about `pid`. `clock_ticks` is the
+/// value of `clock_ticks()`, passed in since `ProcessSampler` keeps it.
+pub fn read_process_detail(pid: i32, clock_ticks: u64) -> io::Result<ProcessDetail> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid))?
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+        .collect();
+
+    Ok(ProcessDetail {
+        pid,
+        status,
+        cmdline: split_nul(&fs::read(format!("/proc/{}/cmdline", pid))?),
+        cwd: fs::read_link(format!("/proc/{}/cwd", pid)),
+        exe: fs::read_link(format!("/proc/{}/exe", pid)),
+        environ: fs::read(format!("/proc/{}/environ", pid)).map(|data| split_nul(&data)),
+        limits: fs::read_to_string(format!("/proc/{}/limits", pid)).and_then(|data| parse_limits(&data)),
+        threads: read_threads(pid),
+        clock_ticks,
+        fds: read_fds(pid),
+        memory: fs::read_to_string(format!("/proc/{}/smaps_rollup", pid)).map(|data| parse_smaps_rollup(&data)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_limits() {
+        let header = "Limit                     Soft Limit           Hard Limit           Units     \n";
+        let limit = |name: &str, soft: &str, hard: &str, units: &str| ProcessLimit {
+            name: name.to_string(),
+            soft: soft.to_string(),
+            hard: hard.to_string(),
+            units: units.to_string(),
+        };
+
+        let cases = [
+            (
+                format!(
+                    "{}\
+Max cpu time              unlimited            unlimited            seconds   \n\
+Max processes             63304                63304                processes \n\
+Max open files            1024                 524288               files     \n",
+                    header
+                ),
+                Some(vec![
+                    limit("Max cpu time", "unlimited", "unlimited", "seconds"),
+                    limit("Max processes", "63304", "63304", "processes"),
+                    limit("Max open files", "1024", "524288", "files"),
+                ]),
+            ),
+            (
+                format!(
+                    "{}Max open files            unlimited            unlimited            files     \n\n",
+                    header
+                ),
+                Some(vec![limit("Max open files", "unlimited", "unlimited", "files")]),
+            ),
+            // Lines cut short leave the missing columns empty
+            (
+                format!("{}Max nice priority         0\nMax realtime\n", header),
+                Some(vec![limit("Max nice priority", "0", "", ""), limit("Max realtime", "", "", "")]),
+            ),
+            (header.to_string(), Some(vec![])),
+            (
+                String::from("Max open files            1024                 524288               files     \n"),
+                None,
+            ),
+            (String::from("Limit                     Soft Limit\n"), None),
+            (String::new(), None),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(parse_limits(&data).ok(), expected, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn parses_smaps_rollup() {
+        let cases = [
+            (
+                "55f0f7c11000-7ffd88aba000 ---p 00000000 00:00 0                          [rollup]\n\
+                 Rss:                1304 kB\n\
+                 Pss:                 392 kB\n\
+                 Swap:                  0 kB\n",
+                vec![("Rss", 1304), ("Pss", 392), ("Swap", 0)],
+            ),
+            // Lines without a number are left out
+            (
+                "55f0f7c11000-7ffd88aba000 ---p 00000000 00:00 0 [rollup]\n\
+                 Rss:                1304 kB\n\
+                 Pss:\n\
+                 Locked:             many kB\n\
+                 no colon here\n",
+                vec![("Rss", 1304)],
+            ),
+            ("55f0f7c11000-7ffd88aba000 ---p 00000000 00:00 0 [rollup]\n", vec![]),
+            ("", vec![]),
+        ];
+
+        for (data, expected) in cases {
+            let expected: Vec<(String, u64)> = expected.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+            assert_eq!(parse_smaps_rollup(data), expected, "{:?}", data);
+        }
+    }
+}
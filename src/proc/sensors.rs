@@ -0,0 +1,285 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where the kernel lists the hardware monitoring chips.
+pub const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SensorKind {
+    Temperature,
+    Fan,
+    Voltage,
+    Power,
+}
+
+impl SensorKind {
+    const ALL: [SensorKind; 4] = [
+        SensorKind::Temperature,
+        SensorKind::Fan,
+        SensorKind::Voltage,
+        SensorKind::Power,
+    ];
+
+    /// Prefix of the attribute files, such as `temp` for `temp1_input`.
+    fn prefix(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "temp",
+            SensorKind::Fan => "fan",
+            SensorKind::Voltage => "in",
+            SensorKind::Power => "power",
+        }
+    }
+
+    /// What the raw values are divided by to get `unit`s. The kernel
+    /// reports millidegrees, RPM, millivolts and microwatts.
+    fn scale(&self) -> f64 {
+        match self {
+            SensorKind::Temperature => 1000.0,
+            SensorKind::Fan => 1.0,
+            SensorKind::Voltage => 1000.0,
+            SensorKind::Power => 1_000_000.0,
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "°C",
+            SensorKind::Fan => "RPM",
+            SensorKind::Voltage => "V",
+            SensorKind::Power => "W",
+        }
+    }
+}
+
+/// One `*_input` attribute of a hwmon chip, in the units of its kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensor {
+    /// Unique over the chips, such as `hwmon2/temp1`
+    pub id: String,
+    /// Name of the chip, such as `coretemp`
+    pub chip: String,
+    /// The `*_label` attribute, or the attribute name when there is none
+    pub label: String,
+    pub kind: SensorKind,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+impl Sensor {
+    /// Whether the value reached one of the thresholds. Fans report a
+    /// maximum speed rather than a limit, so only their critical
+    /// threshold counts.
+    pub fn is_alarming(&self) -> bool {
+        let max = if self.kind == SensorKind::Fan { None } else { self.max };
+        [max, self.crit]
+            .iter()
+            .flatten()
+            .any(|threshold| *threshold > 0.0 && self.value >= *threshold)
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Reads the sensors of one chip. Older drivers put their attributes in
+/// the `device` directory rather than next to `name`.
+fn read_chip(dir: &Path) -> Vec<Sensor> {
+    let chip_id = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let chip = read_trimmed(&dir.join("name")).unwrap_or_else(|| chip_id.clone());
+
+    for attribute_dir in [dir.to_path_buf(), dir.join("device")] {
+        let entries = match fs::read_dir(&attribute_dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        let mut chip_sensors: Vec<(SensorKind, u32, Sensor)> = Vec::new();
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let name = match file_name.to_str().and_then(|name| name.strip_suffix("_input")) {
+                Some(name) => name,
+                None => continue,
+            };
+            let (kind, index) = match SensorKind::ALL.iter().find_map(|kind| {
+                let index = name.strip_prefix(kind.prefix())?.parse::<u32>().ok()?;
+                Some((*kind, index))
+            }) {
+                Some(sensor) => sensor,
+                None => continue,
+            };
+
+            let read_value = |attribute: &str| {
+                read_trimmed(&attribute_dir.join(format!("{}_{}", name, attribute)))
+                    .and_then(|value| value.parse::<f64>().ok())
+                    .map(|value| value / kind.scale())
+            };
+            // Disabled or faulty sensors fail to read
+            let value = match read_value("input") {
+                Some(value) => value,
+                None => continue,
+            };
+
+            chip_sensors.push((
+                kind,
+                index,
+                Sensor {
+                    id: format!("{}/{}", chip_id, name),
+                    chip: chip.clone(),
+                    label: read_trimmed(&attribute_dir.join(format!("{}_label", name)))
+                        .unwrap_or_else(|| name.to_string()),
+                    kind,
+                    value,
+                    max: read_value("max"),
+                    crit: read_value("crit"),
+                },
+            ));
+        }
+
+        if !chip_sensors.is_empty() {
+            chip_sensors.sort_by_key(|(kind, index, _)| (*kind, *index));
+            return chip_sensors.into_iter().map(|(_, _, sensor)| sensor).collect();
+        }
+    }
+
+    Vec::new()
+}
+
+/// Every readable sensor of the hwmon chips under `root`, which is
+/// `HWMON_ROOT` except when pointed at a copy of sysfs.
+pub fn read_sensors(root: &Path) -> io::Result<Vec<Sensor>> {
+    let mut chips: Vec<PathBuf> = fs::read_dir(root)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    // hwmon10 after hwmon9
+    chips.sort_by_key(|path| {
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let index = name.trim_start_matches("hwmon").parse::<u32>().unwrap_or(u32::MAX);
+        (index, name.to_string())
+    });
+
+    Ok(chips.iter().flat_map(|chip| read_chip(chip)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fake hwmon tree under the temp dir, removed when dropped.
+    struct HwmonTree(PathBuf);
+
+    impl HwmonTree {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("hwmon-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            HwmonTree(root)
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    impl Drop for HwmonTree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn reads_labels_scales_and_thresholds() {
+        let tree = HwmonTree::new("labels");
+        tree.write("hwmon0/name", "coretemp\n");
+        tree.write("hwmon0/temp1_input", "45000\n");
+        tree.write("hwmon0/temp1_label", "Package id 0\n");
+        tree.write("hwmon0/temp1_max", "80000\n");
+        tree.write("hwmon0/temp1_crit", "100000\n");
+        tree.write("hwmon0/temp2_input", "-5500\n");
+        tree.write("hwmon0/in0_input", "1200\n");
+        tree.write("hwmon0/fan1_input", "1500\n");
+        tree.write("hwmon0/power1_input", "12500000\n");
+
+        let sensors = read_sensors(&tree.0).unwrap();
+        let summary: Vec<(&str, &str, SensorKind, f64)> = sensors
+            .iter()
+            .map(|s| (s.id.as_str(), s.label.as_str(), s.kind, s.value))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("hwmon0/temp1", "Package id 0", SensorKind::Temperature, 45.0),
+                ("hwmon0/temp2", "temp2", SensorKind::Temperature, -5.5),
+                ("hwmon0/fan1", "fan1", SensorKind::Fan, 1500.0),
+                ("hwmon0/in0", "in0", SensorKind::Voltage, 1.2),
+                ("hwmon0/power1", "power1", SensorKind::Power, 12.5),
+            ]
+        );
+        assert!(sensors.iter().all(|s| s.chip == "coretemp"));
+        assert_eq!((sensors[0].max, sensors[0].crit), (Some(80.0), Some(100.0)));
+        assert_eq!((sensors[1].max, sensors[1].crit), (None, None));
+    }
+
+    #[test]
+    fn falls_back_to_the_device_directory() {
+        let tree = HwmonTree::new("device");
+        tree.write("hwmon1/name", "it8728\n");
+        tree.write("hwmon1/device/temp1_input", "38000\n");
+        tree.write("hwmon1/device/temp1_label", "SYSTIN\n");
+
+        let sensors = read_sensors(&tree.0).unwrap();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].id, "hwmon1/temp1");
+        assert_eq!(sensors[0].chip, "it8728");
+        assert_eq!(sensors[0].label, "SYSTIN");
+        assert_eq!(sensors[0].value, 38.0);
+    }
+
+    #[test]
+    fn skips_sensors_that_cannot_be_read() {
+        let tree = HwmonTree::new("missing");
+        tree.write("hwmon0/name", "nct6775\n");
+        // Only the label and thresholds of a disabled sensor
+        tree.write("hwmon0/temp1_label", "AUXTIN\n");
+        tree.write("hwmon0/temp1_max", "80000\n");
+        tree.write("hwmon0/temp2_input", "\n");
+        tree.write("hwmon0/temp3_input", "not a number\n");
+        tree.write("hwmon0/temp4_input", "41000\n");
+        tree.write("hwmon0/temp4_type", "3\n");
+
+        let sensors = read_sensors(&tree.0).unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["hwmon0/temp4"]);
+    }
+
+    #[test]
+    fn sorts_chips_by_number() {
+        let tree = HwmonTree::new("sort");
+        for chip in ["hwmon10", "hwmon2", "hwmon9"] {
+            tree.write(&format!("{}/name", chip), chip);
+            tree.write(&format!("{}/temp1_input", chip), "30000");
+        }
+
+        let sensors = read_sensors(&tree.0).unwrap();
+        let chips: Vec<&str> = sensors.iter().map(|s| s.chip.as_str()).collect();
+        assert_eq!(chips, ["hwmon2", "hwmon9", "hwmon10"]);
+    }
+
+    #[test]
+    fn fails_without_the_root() {
+        let tree = HwmonTree::new("root");
+        assert!(read_sensors(&tree.0.join("missing")).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use super::invalid_data;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketProtocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+    Unix,
+}
+
+impl SocketProtocol {
+    fn path(&self) -> &'static str {
+        match self {
+            SocketProtocol::Tcp => "/proc/net/tcp",
+            SocketProtocol::Tcp6 => "/proc/net/tcp6",
+            SocketProtocol::Udp => "/proc/net/udp",
+            SocketProtocol::Udp6 => "/proc/net/udp6",
+            SocketProtocol::Unix => "/proc/net/unix",
+        }
+    }
+
+    fn is_udp(&self) -> bool {
+        matches!(self, SocketProtocol::Udp | SocketProtocol::Udp6)
+    }
+}
+
+impl std::fmt::Display for SocketProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SocketProtocol::Tcp => write!(f, "tcp"),
+            SocketProtocol::Tcp6 => write!(f, "tcp6"),
+            SocketProtocol::Udp => write!(f, "udp"),
+            SocketProtocol::Udp6 => write!(f, "udp6"),
+            SocketProtocol::Unix => write!(f, "unix"),
+        }
+    }
+}
+
+/// One socket of /proc/net/{tcp,tcp6,udp,udp6,unix}.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketInfo {
+    pub protocol: SocketProtocol,
+    /// `address:port`, or the path of a unix socket
+    pub local: String,
+    pub remote: String,
+    pub local_port: Option<u16>,
+    pub remote_port: Option<u16>,
+    /// Names as used by `ss`, such as `LISTEN` or `ESTAB`
+    pub state: &'static str,
+    pub inode: u64,
+    /// PID and command of a process with the socket open, when one could
+    /// be found
+    pub owner: Option<(i32, String)>,
+}
+
+impl SocketInfo {
+    /// Like `ss -l`, a bound UDP socket with no remote end counts as
+    /// listening, since UDP servers never reach the `LISTEN` state.
+    pub fn is_listening(&self) -> bool {
+        match self.state {
+            "LISTEN" => true,
+            "UNCONN" => self.protocol.is_udp() && self.remote_port == Some(0),
+            _ => false,
+        }
+    }
+}
+
+fn tcp_state(state: u8) -> &'static str {
+    match state {
+        0x01 => "ESTAB",
+        0x02 => "SYN-SENT",
+        0x03 => "SYN-RECV",
+        0x04 => "FIN-WAIT-1",
+        0x05 => "FIN-WAIT-2",
+        0x06 => "TIME-WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE-WAIT",
+        0x09 => "LAST-ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Parses an `ADDRESS:PORT` field of /proc/net/{tcp,udp}{,6}. The address
+/// is printed as 32-bit words in host byte order.
+fn parse_inet_address(field: &str) -> io::Result<(String, u16)> {
+    let bad = || invalid_data(format!("bad socket address {:?}", field));
+
+    let (address, port) = field.split_once(':').ok_or_else(bad)?;
+    let port = u16::from_str_radix(port, 16).map_err(|_| bad())?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..address.len()).step_by(8) {
+        let word = address.get(i..i + 8).ok_or_else(bad)?;
+        let word = u32::from_str_radix(word, 16).map_err(|_| bad())?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let address = match bytes.len() {
+        4 => Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string(),
+        16 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&bytes);
+            format!("[{}]", Ipv6Addr::from(octets))
+        }
+        _ => return Err(bad()),
+    };
+
+    Ok((address, port))
+}
+
+pub fn parse_inet_sockets(data: &str, protocol: SocketProtocol) -> io::Result<Vec<SocketInfo>> {
+    // Skip the header
+    data.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                return Err(invalid_data(format!("bad {} line {:?}", protocol.path(), line)));
+            }
+
+            let (local, local_port) = parse_inet_address(fields[1])?;
+            let (remote, remote_port) = parse_inet_address(fields[2])?;
+            let state = u8::from_str_radix(fields[3], 16)
+                .map_err(|e| invalid_data(format!("bad socket state {:?}: {}", fields[3], e)))?;
+            let inode = fields[9]
+                .parse()
+                .map_err(|e| invalid_data(format!("bad socket inode {:?}: {}", fields[9], e)))?;
+
+            Ok(SocketInfo {
+                protocol,
+                local: format!("{}:{}", local, local_port),
+                remote: format!("{}:{}", remote, remote_port),
+                local_port: Some(local_port),
+                remote_port: Some(remote_port),
+                // UDP sockets reuse the TCP states, and an unconnected one
+                // is reported as CLOSE
+                state: match tcp_state(state) {
+                    "CLOSE" if protocol.is_udp() => "UNCONN",
+                    state => state,
+                },
+                inode,
+                owner: None,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_unix_sockets(data: &str) -> io::Result<Vec<SocketInfo>> {
+    /// `__SO_ACCEPTCON`, set on listening sockets
+    const ACCEPT_CONNECTIONS: u32 = 1 << 16;
+
+    // Skip the header
+    data.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            // Num RefCount Protocol Flags Type St Inode [Path]
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 7 {
+                return Err(invalid_data(format!("bad /proc/net/unix line {:?}", line)));
+            }
+
+            let flags = u32::from_str_radix(fields[3], 16)
+                .map_err(|e| invalid_data(format!("bad unix socket flags {:?}: {}", fields[3], e)))?;
+            let state = match fields[5] {
+                _ if flags & ACCEPT_CONNECTIONS != 0 => "LISTEN",
+                "01" => "UNCONN",
+                "02" => "CONNECTING",
+                "03" => "ESTAB",
+                "04" => "DISCONNECTING",
+                _ => "UNKNOWN",
+            };
+            let inode = fields[6]
+                .parse()
+                .map_err(|e| invalid_data(format!("bad unix socket inode {:?}: {}", fields[6], e)))?;
+
+            Ok(SocketInfo {
+                protocol: SocketProtocol::Unix,
+                local: fields.get(7).map(|path| path.to_string()).unwrap_or_default(),
+                remote: String::new(),
+                local_port: None,
+                remote_port: None,
+                state,
+                inode,
+                owner: None,
+            })
+        })
+        .collect()
+}
+
+/// Maps socket inodes to the PID and command of a process that has them
+/// open, by going through /proc/[pid]/fd. Only the processes whose file
+/// descriptors can be read are found, which without privileges is those
+/// of the current user.
+pub fn read_socket_owners() -> io::Result<HashMap<u64, (i32, String)>> {
+    let mut owners = HashMap::new();
+
+    for entry in fs::read_dir("/proc")? {
+        let entry = entry?;
+        let pid: i32 = match entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+            Some(pid) => pid,
+            None => continue,
+        };
+        let fds = match fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            // Exited, or not ours
+            Err(_) => continue,
+        };
+
+        let mut command = None;
+        for fd in fds.flatten() {
+            let inode = match fs::read_link(fd.path()) {
+                Ok(target) => target
+                    .to_str()
+                    .and_then(|target| target.strip_prefix("socket:["))
+                    .and_then(|target| target.strip_suffix(']'))
+                    .and_then(|inode| inode.parse::<u64>().ok()),
+                Err(_) => None,
+            };
+            if let Some(inode) = inode {
+                let command = command.get_or_insert_with(|| {
+                    fs::read_to_string(entry.path().join("comm"))
+                        .map(|comm| comm.trim_end().to_string())
+                        .unwrap_or_default()
+                });
+                owners.entry(inode).or_insert_with(|| (pid, command.clone()));
+            }
+        }
+    }
+
+    Ok(owners)
+}
+
+/// Every TCP, UDP and unix socket, with its owner when it can be found.
+pub fn read_sockets() -> io::Result<Vec<SocketInfo>> {
+    let mut sockets = Vec::new();
+
+    for protocol in [
+        SocketProtocol::Tcp,
+        SocketProtocol::Tcp6,
+        SocketProtocol::Udp,
+        SocketProtocol::Udp6,
+        SocketProtocol::Unix,
+    ] {
+        let data = match fs::read_to_string(protocol.path()) {
+            Ok(data) => data,
+            // The IPv6 files are missing when IPv6 is disabled
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        sockets.extend(match protocol {
+            SocketProtocol::Unix => parse_unix_sockets(&data)?,
+            _ => parse_inet_sockets(&data, protocol)?,
+        });
+    }
+
+    let owners = read_socket_owners()?;
+    for socket in &mut sockets {
+        // Sockets in TIME-WAIT and the like have no inode
+        if socket.inode != 0 {
+            socket.owner = owners.get(&socket.inode).cloned();
+        }
+    }
+
+    Ok(sockets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The addresses are printed in host byte order, as on x86 and arm64
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_inet_addresses() {
+        let cases = [
+            ("0100007F:0050", Some(("127.0.0.1", 80))),
+            ("00000000:0000", Some(("0.0.0.0", 0))),
+            ("0101A8C0:FFFF", Some(("192.168.1.1", 65535))),
+            ("00000000000000000000000001000000:1F90", Some(("[::1]", 8080))),
+            ("0000000000000000FFFF00000100007F:0016", Some(("[::ffff:127.0.0.1]", 22))),
+            ("B80D0120000000000000000001000000:01BB", Some(("[2001:db8::1]", 443))),
+            ("0100007F", None),
+            ("0100007F:10000", None),
+            ("0100007:0050", None),
+            ("0100007G:0050", None),
+            ("0100007F00:0050", None),
+            ("000000000000000000000000:0050", None),
+        ];
+
+        for (field, expected) in cases {
+            let parsed = parse_inet_address(field).ok();
+            let parsed = parsed.as_ref().map(|(address, port)| (address.as_str(), *port));
+            assert_eq!(parsed, expected, "{:?}", field);
+        }
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_inet_sockets() {
+        let data = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:BC8F 00000000:0000 07 00000000:00000000 00:00000000 00000000 65534        0 969 1 00000000264bfab7 100 0 0 10 0
+   1: 0100007F:0035 0100007F:D431 01 00000000:00000000 00:00000000 00000000     0        0 662 2 00000000c9b28a42 0
+";
+
+        let sockets = parse_inet_sockets(data, SocketProtocol::Udp).unwrap();
+        let summary: Vec<(&str, &str, &str, u64)> = sockets
+            .iter()
+            .map(|s| (s.local.as_str(), s.remote.as_str(), s.state, s.inode))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("127.0.0.1:48271", "0.0.0.0:0", "UNCONN", 969),
+                ("127.0.0.1:53", "127.0.0.1:54321", "ESTAB", 662),
+            ]
+        );
+        let listening: Vec<bool> = sockets.iter().map(SocketInfo::is_listening).collect();
+        assert_eq!(listening, [true, false]);
+        assert!(parse_inet_sockets("header\n   0: 0100007F:BC8F 00000000:0000 0A\n", SocketProtocol::Tcp).is_err());
+    }
+
+    #[test]
+    fn parses_unix_sockets() {
+        let data = "Num       RefCount Protocol Flags    Type St Inode Path
+000000004240f224: 00000002 00000000 00010000 0001 01 42784 /run/dbus/system_bus_socket
+000000009a3f248d: 00000003 00000000 00000000 0001 03 42785
+";
+
+        let sockets = parse_unix_sockets(data).unwrap();
+        let summary: Vec<(&str, &str, u64)> = sockets.iter().map(|s| (s.local.as_str(), s.state, s.inode)).collect();
+        assert_eq!(summary, [("/run/dbus/system_bus_socket", "LISTEN", 42784), ("", "ESTAB", 42785)]);
+        let listening: Vec<bool> = sockets.iter().map(SocketInfo::is_listening).collect();
+        assert_eq!(listening, [true, false]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::io;
+
+use iced::widget::{column, row, text, Column};
+use iced::{Element, Length};
+
+use crate::proc::ProcessDetail;
+use crate::process_table::format_rss;
+use crate::Message;
+
+const KEY_WIDTH: Length = Length::Units(160);
+
+fn heading<'a>(title: String) -> Element<'a, Message> {
+    text(title).size(18).into()
+}
+
+fn key_value<'a>(key: &str, value: String) -> Element<'a, Message> {
+    row![
+        text(key).size(14).width(KEY_WIDTH),
+        text(value).size(14).width(Length::Fill),
+    ]
+    .spacing(5)
+    .into()
+}
+
+/// Shows the section, or why it could not be read.
+fn section<'a, T>(
+    title: &str,
+    data: &'a io::Result<T>,
+    count: impl Fn(&T) -> Option<usize>,
+    view: impl Fn(&'a T) -> Element<'a, Message>,
+) -> Element<'a, Message> {
+    match data {
+        Ok(data) => {
+            let title = match count(data) {
+                Some(count) => format!("{} ({})", title, count),
+                None => title.to_string(),
+            };
+            column![heading(title), view(data)].spacing(5).into()
+        }
+        Err(e) => column![
+            heading(title.to_string()),
+            text(format!("Unavailable: {}", e)).size(14)
+        ]
+        .spacing(5)
+        .into(),
+    }
+}
+
+pub fn view(pid: i32, detail: Option<&io::Result<ProcessDetail>>) -> Element<'_, Message> {
+    let detail = match detail {
+        None => return text("Loading...").into(),
+        Some(Err(e)) if e.kind() == io::ErrorKind::NotFound => {
+            return text(format!("Process {} has exited", pid)).into()
+        }
+        Some(Err(e)) => return text(format!("Failed to read process {}: {}", pid, e)).into(),
+        Some(Ok(detail)) => detail,
+    };
+
+    let status = detail.status.iter().fold(Column::new(), |col, (key, value)| {
+        col.push(key_value(key, value.clone()))
+    });
+
+    let cmdline = if detail.cmdline.is_empty() {
+        String::from("(kernel thread)")
+    } else {
+        detail.cmdline.join(" ")
+    };
+    let path = |path: &io::Result<std::path::PathBuf>| match path {
+        Ok(path) => path.display().to_string(),
+        Err(e) => format!("Unavailable: {}", e),
+    };
+
+    let limits = section("Limits", &detail.limits, |_| None, |limits| {
+        let header = row![
+            text("Limit").size(14).width(Length::Units(220)),
+            text("Soft").size(14).width(Length::Units(120)),
+            text("Hard").size(14).width(Length::Units(120)),
+            text("Units").size(14),
+        ];
+        limits
+            .iter()
+            .fold(column![header], |col, limit| {
+                col.push(row![
+                    text(&limit.name).size(14).width(Length::Units(220)),
+                    text(&limit.soft).size(14).width(Length::Units(120)),
+                    text(&limit.hard).size(14).width(Length::Units(120)),
+                    text(&limit.units).size(14),
+                ])
+            })
+            .into()
+    });
+
+    let threads = section("Threads", &detail.threads, |t| Some(t.len()), |threads| {
+        let header = row![
+            text("TID").size(14).width(Length::Units(80)),
+            text("Name").size(14).width(Length::Units(180)),
+            text("S").size(14).width(Length::Units(30)),
+            text("CPU time").size(14),
+        ];
+        threads
+            .iter()
+            .fold(column![header], |col, thread| {
+                col.push(row![
+                    text(thread.tid).size(14).width(Length::Units(80)),
+                    text(&thread.name).size(14).width(Length::Units(180)),
+                    text(thread.state).size(14).width(Length::Units(30)),
+                    text(format!("{:.2}s", thread.cpu_time as f64 / detail.clock_ticks as f64)).size(14),
+                ])
+            })
+            .into()
+    });
+
+    let fds = section("Open files", &detail.fds, |fds| Some(fds.len()), |fds| {
+        fds.iter()
+            .fold(Column::new(), |col, (fd, target)| col.push(key_value(&fd.to_string(), target.clone())))
+            .into()
+    });
+
+    let memory = section("Memory map summary", &detail.memory, |_| None, |memory| {
+        memory
+            .iter()
+            .fold(Column::new(), |col, (key, kib)| col.push(key_value(key, format_rss(*kib))))
+            .into()
+    });
+
+    let environ = section("Environment", &detail.environ, |env| Some(env.len()), |environ| {
+        environ
+            .iter()
+            .fold(Column::new(), |col, var| col.push(text(var).size(14)))
+            .into()
+    });
+
+    column![
+        heading(format!("Process {}", detail.pid)),
+        key_value("Command line", cmdline),
+        key_value("Working directory", path(&detail.cwd)),
+        key_value("Executable", path(&detail.exe)),
+        heading(String::from("Status")),
+        status,
+        memory,
+        threads,
+        limits,
+        fds,
+        environ,
+    ]
+    .spacing(10)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,952 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use iced::theme;
+use iced::widget::canvas::{self, Canvas, Cursor, Frame, Geometry, Path, Stroke};
+use iced::widget::pane_grid;
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input, Column, Row, Space};
+use iced::{Alignment, Color, Element, Length, Rectangle, Theme};
+use regex::{Regex, RegexBuilder};
+
+use crate::proc::{self, IoPriority, ProcessInfo, Signal};
+use crate::Message;
+
+/// Columns of the process table, which are also the available sort keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessColumn {
+    Pid,
+    User,
+    Cpu,
+    Mem,
+    Rss,
+    Threads,
+    State,
+    StartTime,
+    Command,
+}
+
+impl ProcessColumn {
+    const ALL: [ProcessColumn; 9] = [
+        ProcessColumn::Pid,
+        ProcessColumn::User,
+        ProcessColumn::Cpu,
+        ProcessColumn::Mem,
+        ProcessColumn::Rss,
+        ProcessColumn::Threads,
+        ProcessColumn::State,
+        ProcessColumn::StartTime,
+        ProcessColumn::Command,
+    ];
+
+    fn width(&self) -> Length {
+        match self {
+            ProcessColumn::Pid => Length::Units(70),
+            ProcessColumn::User => Length::Units(90),
+            ProcessColumn::Cpu => Length::Units(120),
+            ProcessColumn::Mem => Length::Units(65),
+            ProcessColumn::Rss => Length::Units(130),
+            ProcessColumn::Threads => Length::Units(55),
+            ProcessColumn::State => Length::Units(45),
+            ProcessColumn::StartTime => Length::Units(80),
+            ProcessColumn::Command => Length::Fill,
+        }
+    }
+
+    /// Whether the first click on the header sorts from the largest value,
+    /// which is what you want for the resource columns.
+    fn descending_by_default(&self) -> bool {
+        matches!(
+            self,
+            ProcessColumn::Cpu | ProcessColumn::Mem | ProcessColumn::Rss | ProcessColumn::Threads
+        )
+    }
+
+    fn compare(&self, a: &ProcessInfo, b: &ProcessInfo) -> std::cmp::Ordering {
+        match self {
+            ProcessColumn::Pid => a.pid.cmp(&b.pid),
+            ProcessColumn::User => a.user.cmp(&b.user),
+            ProcessColumn::Cpu => a.cpu_percent.total_cmp(&b.cpu_percent),
+            ProcessColumn::Mem => a.mem_percent.total_cmp(&b.mem_percent),
+            ProcessColumn::Rss => a.rss.cmp(&b.rss),
+            ProcessColumn::Threads => a.num_threads.cmp(&b.num_threads),
+            ProcessColumn::State => a.state.cmp(&b.state),
+            ProcessColumn::StartTime => a.start_time.cmp(&b.start_time),
+            ProcessColumn::Command => a.command().cmp(&b.command()),
+        }
+    }
+
+    /// Like `compare`, but the resource columns use `usage`, which in tree
+    /// mode covers the whole subtree.
+    fn compare_usage(&self, a: (&ProcessInfo, &Usage), b: (&ProcessInfo, &Usage)) -> std::cmp::Ordering {
+        match self {
+            ProcessColumn::Cpu => a.1.cpu_percent.total_cmp(&b.1.cpu_percent),
+            ProcessColumn::Mem => a.1.mem_percent.total_cmp(&b.1.mem_percent),
+            ProcessColumn::Rss => a.1.rss.cmp(&b.1.rss),
+            _ => self.compare(a.0, b.0),
+        }
+    }
+
+    fn cell(&self, process: &ProcessInfo, usage: &Usage) -> String {
+        match self {
+            ProcessColumn::Pid => process.pid.to_string(),
+            ProcessColumn::User => process.user.clone(),
+            ProcessColumn::Cpu => format!("{:.1}", usage.cpu_percent),
+            ProcessColumn::Mem => format!("{:.1}", usage.mem_percent),
+            ProcessColumn::Rss => format_rss(usage.rss),
+            ProcessColumn::Threads => process.num_threads.to_string(),
+            ProcessColumn::State => process.state.to_string(),
+            ProcessColumn::StartTime => format_start_time(process),
+            ProcessColumn::Command => process.command(),
+        }
+    }
+}
+
+impl std::fmt::Display for ProcessColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ProcessColumn::Pid => "PID",
+                ProcessColumn::User => "User",
+                ProcessColumn::Cpu => "CPU%",
+                ProcessColumn::Mem => "MEM%",
+                ProcessColumn::Rss => "RSS",
+                ProcessColumn::Threads => "Thr",
+                ProcessColumn::State => "S",
+                ProcessColumn::StartTime => "Start",
+                ProcessColumn::Command => "Command",
+            }
+        )
+    }
+}
+
+pub fn format_rss(kib: u64) -> String {
+    if kib < 1024 {
+        format!("{}K", kib)
+    } else if kib < 1048576 {
+        format!("{:.1}M", kib as f64 / 1024.0)
+    } else {
+        format!("{:.2}G", kib as f64 / 1048576.0)
+    }
+}
+
+/// Time of day for processes started today, the date otherwise, like ps.
+fn format_start_time(process: &ProcessInfo) -> String {
+    let start = Local.from_utc_datetime(&process.start_time.naive_utc());
+    if start.date_naive() == Local::now().date_naive() {
+        start.format("%H:%M:%S").to_string()
+    } else {
+        start.format("%b %d").to_string()
+    }
+}
+
+/// Width of the sparklines drawn next to the CPU% and RSS values
+const SPARKLINE_WIDTH: u16 = 60;
+
+/// Recent CPU% and RSS of one process, kept in a rolling window the same
+/// way `CPUSeries::push_data` keeps the usage of a core.
+struct ProcessHistory {
+    data_points: VecDeque<(DateTime<Utc>, f64, u64)>,
+    limit: Duration,
+}
+
+impl ProcessHistory {
+    fn new() -> Self {
+        Self {
+            data_points: VecDeque::new(),
+            limit: Duration::from_secs(60),
+        }
+    }
+
+    fn push_data(&mut self, time: DateTime<Utc>, cpu_percent: f64, rss: u64) {
+        self.data_points.push_front((time, cpu_percent, rss));
+        let limit = chrono::Duration::from_std(self.limit).unwrap_or_else(|_| chrono::Duration::zero());
+        while matches!(self.data_points.back(), Some((t, _, _)) if time - *t > limit) {
+            self.data_points.pop_back();
+        }
+    }
+
+    fn cpu_sparkline(&self) -> Sparkline {
+        let points: Vec<_> = self.data_points.iter().map(|(time, cpu, _)| (*time, *cpu)).collect();
+        // Multi-threaded processes can go over 100%
+        let max = points.iter().map(|(_, cpu)| *cpu).fold(100.0, f64::max);
+        Sparkline::new(points, max, self.limit, Color::from_rgb8(181, 32, 186))
+    }
+
+    fn rss_sparkline(&self) -> Sparkline {
+        let points: Vec<_> = self.data_points.iter().map(|(time, _, rss)| (*time, *rss as f64)).collect();
+        let max = points.iter().map(|(_, rss)| *rss).fold(1.0, f64::max);
+        Sparkline::new(points, max, self.limit, Color::from_rgb8(38, 110, 210))
+    }
+}
+
+/// A tiny line chart without axes, with the newest point on the right.
+struct Sparkline {
+    /// Newest first, like the data points of the charts
+    points: Vec<(DateTime<Utc>, f64)>,
+    max: f64,
+    window: Duration,
+    color: Color,
+}
+
+impl Sparkline {
+    fn new(points: Vec<(DateTime<Utc>, f64)>, max: f64, window: Duration, color: Color) -> Self {
+        Self { points, max, window, color }
+    }
+
+    fn view<'a>(self) -> Element<'a, Message> {
+        Canvas::new(self)
+            .width(Length::Units(SPARKLINE_WIDTH))
+            .height(Length::Units(14))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for Sparkline {
+    type State = ();
+
+    fn draw(&self, _state: &(), _theme: &Theme, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+
+        if let Some((newest, _)) = self.points.first() {
+            let window_ms = self.window.as_millis().max(1) as f32;
+            let path = Path::new(|builder| {
+                for (i, (time, value)) in self.points.iter().enumerate() {
+                    let age = (*newest - *time).num_milliseconds() as f32 / window_ms;
+                    let x = bounds.width * (1.0 - age.min(1.0));
+                    let y = bounds.height * (1.0 - (value / self.max) as f32).clamp(0.0, 1.0);
+                    if i == 0 {
+                        builder.move_to([x, y].into());
+                    } else {
+                        builder.line_to([x, y].into());
+                    }
+                }
+            });
+            frame.stroke(&path, Stroke::default().with_color(self.color).with_width(1.0));
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Resources used by a process, or by a process and all its descendants.
+#[derive(Debug, Clone, Copy, Default)]
+struct Usage {
+    cpu_percent: f64,
+    mem_percent: f64,
+    rss: u64,
+}
+
+impl Usage {
+    fn of(process: &ProcessInfo) -> Self {
+        Self {
+            cpu_percent: process.cpu_percent,
+            mem_percent: process.mem_percent,
+            rss: process.rss,
+        }
+    }
+
+    fn add(&mut self, other: &Usage) {
+        self.cpu_percent += other.cpu_percent;
+        self.mem_percent += other.mem_percent;
+        self.rss += other.rss;
+    }
+}
+
+/// A line of the process table.
+struct TableRow<'a> {
+    process: &'a ProcessInfo,
+    usage: Usage,
+    /// Nesting level in tree mode, always 0 in the flat list
+    depth: usize,
+    /// Whether the subtree is collapsed, for processes with children shown
+    /// in tree mode
+    collapsed: Option<bool>,
+}
+
+/// Something that can be done to a process from the table. Every action
+/// has to be confirmed before it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessAction {
+    Signal(Signal),
+    Renice(i32),
+    SetIoPriority(IoPriority),
+}
+
+impl ProcessAction {
+    fn apply(&self, pid: i32) -> std::io::Result<()> {
+        match self {
+            ProcessAction::Signal(signal) => proc::send_signal(pid, *signal),
+            ProcessAction::Renice(nice) => proc::set_nice(pid, *nice),
+            ProcessAction::SetIoPriority(priority) => proc::set_io_priority(pid, *priority),
+        }
+    }
+
+    fn describe(&self, process: &ProcessInfo) -> String {
+        let target = format!("PID {} ({})", process.pid, process.name);
+        match self {
+            ProcessAction::Signal(signal) => format!("send {} to {}", signal, target),
+            ProcessAction::Renice(nice) => format!("set the nice value of {} to {}", target, nice),
+            ProcessAction::SetIoPriority(priority) => {
+                format!("set the I/O priority of {} to {}", target, priority)
+            }
+        }
+    }
+}
+
+/// Action waiting for confirmation, and the process it applies to.
+struct PendingAction {
+    pid: i32,
+    /// Start time of the process when the action was asked for
+    start_ticks: u64,
+    action: ProcessAction,
+}
+
+fn describe_error(error: &std::io::Error, pid: i32) -> String {
+    match error.raw_os_error() {
+        Some(libc::EPERM) | Some(libc::EACCES) => format!(
+            "Permission denied: PID {} belongs to another user, or the change needs root",
+            pid
+        ),
+        Some(libc::ESRCH) => format!("PID {} no longer exists", pid),
+        _ => error.to_string(),
+    }
+}
+
+/// Text typed in the filter bar of the process table.
+#[derive(Default)]
+struct ProcessFilter {
+    text: String,
+    use_regex: bool,
+    /// Compiled `text` when `use_regex` is set
+    regex: Option<Result<Regex, regex::Error>>,
+}
+
+impl ProcessFilter {
+    fn set(&mut self, text: String, use_regex: bool) {
+        self.regex = if use_regex && !text.is_empty() {
+            Some(RegexBuilder::new(&text).case_insensitive(true).build())
+        } else {
+            None
+        };
+        self.text = text;
+        self.use_regex = use_regex;
+    }
+
+    /// Whether the name, command line, user or PID of `process` match.
+    /// An invalid regex matches everything, so the list does not go blank
+    /// while it is being typed.
+    fn matches(&self, process: &ProcessInfo) -> bool {
+        if self.text.is_empty() {
+            return true;
+        }
+
+        let pid = process.pid.to_string();
+        let fields = [process.name.as_str(), process.cmdline.as_str(), process.user.as_str(), pid.as_str()];
+
+        match &self.regex {
+            Some(Ok(regex)) => fields.iter().any(|field| regex.is_match(field)),
+            Some(Err(_)) => true,
+            None => {
+                let text = self.text.to_lowercase();
+                fields.iter().any(|field| field.to_lowercase().contains(&text))
+            }
+        }
+    }
+}
+
+pub struct ProcessTable {
+    processes: Vec<ProcessInfo>,
+    sort_column: ProcessColumn,
+    sort_descending: bool,
+    filter: ProcessFilter,
+    history: HashMap<i32, ProcessHistory>,
+    tree_mode: bool,
+    /// Processes whose children are hidden in tree mode
+    collapsed: HashSet<i32>,
+    selected: Option<i32>,
+    /// Action waiting for confirmation
+    pending_action: Option<PendingAction>,
+    /// Outcome of the last confirmed action
+    action_result: Option<String>,
+}
+
+impl Default for ProcessTable {
+    fn default() -> Self {
+        Self {
+            processes: vec![],
+            sort_column: ProcessColumn::Cpu,
+            sort_descending: true,
+            filter: ProcessFilter::default(),
+            history: HashMap::new(),
+            tree_mode: false,
+            collapsed: HashSet::new(),
+            selected: None,
+            pending_action: None,
+            action_result: None,
+        }
+    }
+}
+
+impl ProcessTable {
+    /// Takes the processes of a new snapshot from the collector.
+    pub fn update(&mut self, now: DateTime<Utc>, processes: &[ProcessInfo]) {
+        let pids: HashSet<i32> = processes.iter().map(|p| p.pid).collect();
+        self.history.retain(|pid, _| pids.contains(pid));
+        for process in processes {
+            self.history
+                .entry(process.pid)
+                .or_insert_with(ProcessHistory::new)
+                .push_data(now, process.cpu_percent, process.rss);
+        }
+
+        self.processes = processes.to_vec();
+        self.sort();
+    }
+
+    /// Forgets the processes and their usage history, keeping the sort
+    /// order, filter and selection.
+    pub fn clear_history(&mut self) {
+        self.processes.clear();
+        self.history.clear();
+    }
+
+    /// Sorts by `column`, or flips the order if it is already the sort key.
+    pub fn sort_by(&mut self, column: ProcessColumn) {
+        if self.sort_column == column {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort_column = column;
+            self.sort_descending = column.descending_by_default();
+        }
+        self.sort();
+    }
+
+    pub fn set_filter(&mut self, text: String) {
+        let use_regex = self.filter.use_regex;
+        self.filter.set(text, use_regex);
+    }
+
+    pub fn set_filter_regex(&mut self, use_regex: bool) {
+        let text = std::mem::take(&mut self.filter.text);
+        self.filter.set(text, use_regex);
+    }
+
+    pub fn set_tree_mode(&mut self, tree_mode: bool) {
+        self.tree_mode = tree_mode;
+    }
+
+    /// Collapses or expands the children of `pid` in tree mode.
+    pub fn toggle_subtree(&mut self, pid: i32) {
+        if !self.collapsed.remove(&pid) {
+            self.collapsed.insert(pid);
+        }
+    }
+
+    pub fn select(&mut self, pid: i32) {
+        self.selected = Some(pid);
+        self.pending_action = None;
+        self.action_result = None;
+    }
+
+    /// Asks for confirmation before running `action` on the selected process.
+    pub fn request_action(&mut self, action: ProcessAction) {
+        let process = match self.selected.and_then(|pid| self.processes.iter().find(|p| p.pid == pid)) {
+            Some(process) => process,
+            None => return,
+        };
+        self.pending_action = Some(PendingAction {
+            pid: process.pid,
+            start_ticks: process.start_ticks,
+            action,
+        });
+        self.action_result = None;
+    }
+
+    pub fn cancel_action(&mut self) {
+        self.pending_action = None;
+    }
+
+    pub fn confirm_action(&mut self) {
+        if let Some(PendingAction { pid, start_ticks, action }) = self.pending_action.take() {
+            // The process may have exited while the prompt was up and its
+            // PID been given to another one, which must not be touched
+            let result = match proc::read_start_ticks(pid) {
+                Ok(ticks) if ticks == start_ticks => action.apply(pid),
+                Ok(_) => {
+                    self.action_result = Some(format!("PID {} has exited and now belongs to another process", pid));
+                    return;
+                }
+                // Gone from /proc, the same as kill would report
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    Err(std::io::Error::from_raw_os_error(libc::ESRCH))
+                }
+                Err(e) => Err(e),
+            };
+            self.action_result = Some(match result {
+                Ok(()) => match self.processes.iter().find(|p| p.pid == pid) {
+                    Some(process) => format!("Done: {}", action.describe(process)),
+                    None => String::from("Done"),
+                },
+                Err(e) => describe_error(&e, pid),
+            });
+        }
+    }
+
+    fn sort(&mut self) {
+        let column = self.sort_column;
+        if self.sort_descending {
+            self.processes.sort_by(|a, b| column.compare(b, a));
+        } else {
+            self.processes.sort_by(|a, b| column.compare(a, b));
+        }
+    }
+
+    fn flat_rows(&self) -> Vec<TableRow<'_>> {
+        self.processes
+            .iter()
+            .filter(|process| self.filter.matches(process))
+            .map(|process| TableRow {
+                process,
+                usage: Usage::of(process),
+                depth: 0,
+                collapsed: None,
+            })
+            .collect()
+    }
+
+    /// Rows of the process tree built from the parent PIDs, depth first.
+    /// Each row carries the usage of its whole subtree, and siblings are
+    /// sorted by it. While filtering, the ancestors of the matching
+    /// processes are kept so that they still show up in place.
+    fn tree_rows(&self) -> Vec<TableRow<'_>> {
+        let index: HashMap<i32, usize> = self
+            .processes
+            .iter()
+            .enumerate()
+            .map(|(i, process)| (process.pid, i))
+            .collect();
+
+        let mut children: Vec<Vec<usize>> = vec![vec![]; self.processes.len()];
+        let mut roots = vec![];
+        for (i, process) in self.processes.iter().enumerate() {
+            match index.get(&process.ppid) {
+                Some(&parent) if process.ppid != process.pid => children[parent].push(i),
+                _ => roots.push(i),
+            }
+        }
+
+        // Usage and filter match of every subtree, computed bottom up
+        fn visit(table: &ProcessTable, children: &[Vec<usize>], i: usize, usage: &mut [Usage], matches: &mut [bool]) {
+            let process = &table.processes[i];
+            usage[i] = Usage::of(process);
+            matches[i] = table.filter.matches(process);
+            for &child in &children[i] {
+                visit(table, children, child, usage, matches);
+                let child_usage = usage[child];
+                usage[i].add(&child_usage);
+                matches[i] |= matches[child];
+            }
+        }
+
+        let mut usage = vec![Usage::default(); self.processes.len()];
+        let mut matches = vec![false; self.processes.len()];
+        for &root in &roots {
+            visit(self, &children, root, &mut usage, &mut matches);
+        }
+
+        let column = self.sort_column;
+        let sort = |nodes: &mut Vec<usize>| {
+            nodes.retain(|&i| matches[i]);
+            nodes.sort_by(|&a, &b| {
+                let a = (&self.processes[a], &usage[a]);
+                let b = (&self.processes[b], &usage[b]);
+                if self.sort_descending {
+                    column.compare_usage(b, a)
+                } else {
+                    column.compare_usage(a, b)
+                }
+            });
+        };
+
+        for nodes in children.iter_mut() {
+            sort(nodes);
+        }
+        sort(&mut roots);
+
+        let mut rows = vec![];
+        // Reversed so that popping from the stack keeps the sort order
+        let mut stack: Vec<(usize, usize)> = roots.iter().rev().map(|&i| (i, 0)).collect();
+        while let Some((i, depth)) = stack.pop() {
+            let process = &self.processes[i];
+            let collapsed = self.collapsed.contains(&process.pid);
+
+            rows.push(TableRow {
+                process,
+                usage: usage[i],
+                depth,
+                collapsed: if children[i].is_empty() { None } else { Some(collapsed) },
+            });
+
+            if !collapsed {
+                stack.extend(children[i].iter().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+
+        rows
+    }
+
+    pub fn view(&self, pane: pane_grid::Pane) -> Element<'_, Message> {
+        if self.processes.is_empty() {
+            return text("Loading...").into();
+        }
+
+        let rows = if self.tree_mode {
+            self.tree_rows()
+        } else {
+            self.flat_rows()
+        };
+
+        let status = match &self.filter.regex {
+            Some(Err(e)) => format!("Invalid regex: {}", e.to_string().lines().last().unwrap_or_default()),
+            _ => format!("{} of {} processes", rows.len(), self.processes.len()),
+        };
+
+        let filter_bar = row![
+            text_input(
+                "Filter by name, command, user or PID",
+                &self.filter.text,
+                move |text| Message::ProcessFilterChanged(pane, text),
+            )
+            .padding(5)
+            .size(14),
+            checkbox("Regex", self.filter.use_regex, move |use_regex| {
+                Message::ProcessFilterRegexToggled(pane, use_regex)
+            })
+            .size(14)
+            .text_size(14),
+            checkbox("Tree", self.tree_mode, move |tree_mode| {
+                Message::ProcessTreeModeToggled(pane, tree_mode)
+            })
+            .size(14)
+            .text_size(14),
+            text(status).size(14),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let header = ProcessColumn::ALL
+            .iter()
+            .fold(Row::new().spacing(5), |header, column| {
+                let title = if *column != self.sort_column {
+                    column.to_string()
+                } else if self.sort_descending {
+                    format!("{} ▼", column)
+                } else {
+                    format!("{} ▲", column)
+                };
+
+                header.push(
+                    button(text(title).size(14))
+                        .style(theme::Button::Text)
+                        .padding(0)
+                        .width(column.width())
+                        .on_press(Message::ProcessSortSelected(pane, *column)),
+                )
+            });
+
+        let rows = rows.iter().fold(Column::new(), |rows, row| {
+            rows.push(self.view_row(pane, row))
+        });
+
+        let mut content = column![filter_bar].spacing(5).width(Length::Fill);
+        if let Some(actions) = self.view_actions(pane) {
+            content = content.push(actions);
+        }
+        if let Some(result) = &self.action_result {
+            content = content.push(text(result).size(14));
+        }
+
+        content.push(header).push(rows).into()
+    }
+
+    fn view_row(&self, pane: pane_grid::Pane, row: &TableRow<'_>) -> Element<'_, Message> {
+        let process = row.process;
+        let cells = ProcessColumn::ALL.iter().fold(row![].spacing(5), |cells, column| {
+            let cell = text(column.cell(process, &row.usage)).size(14);
+
+            // The sparklines follow the process itself, even when the value
+            // next to them is the total of its subtree
+            let sparkline = match (column, self.history.get(&process.pid)) {
+                (ProcessColumn::Cpu, Some(history)) => Some(history.cpu_sparkline()),
+                (ProcessColumn::Rss, Some(history)) => Some(history.rss_sparkline()),
+                _ => None,
+            };
+            if let Some(sparkline) = sparkline {
+                return cells.push(
+                    row![cell.width(Length::Fill), sparkline.view()]
+                        .spacing(5)
+                        .align_items(Alignment::Center)
+                        .width(column.width()),
+                );
+            }
+
+            if *column != ProcessColumn::Command || !self.tree_mode {
+                return cells.push(cell.width(column.width()));
+            }
+
+            let expander: Element<'_, Message> = match row.collapsed {
+                Some(collapsed) => button(text(if collapsed { "▸" } else { "▾" }).size(14))
+                    .style(theme::Button::Text)
+                    .padding(0)
+                    .width(Length::Units(14))
+                    .on_press(Message::ProcessSubtreeToggled(pane, process.pid))
+                    .into(),
+                None => Space::with_width(Length::Units(14)).into(),
+            };
+
+            cells.push(
+                row![
+                    Space::with_width(Length::Units(row.depth as u16 * 12)),
+                    expander,
+                    cell,
+                ]
+                .spacing(2)
+                .width(column.width()),
+            )
+        });
+
+        button(cells)
+            .style(if self.selected == Some(process.pid) {
+                theme::Button::Secondary
+            } else {
+                theme::Button::Text
+            })
+            .padding(0)
+            .width(Length::Fill)
+            .on_press(Message::ProcessSelected(pane, process.pid))
+            .into()
+    }
+
+    /// Buttons for the selected process, or the confirmation prompt for the
+    /// action about to run.
+    fn view_actions(&self, pane: pane_grid::Pane) -> Option<Element<'_, Message>> {
+        let pid = self.selected?;
+        let process = match self.processes.iter().find(|p| p.pid == pid) {
+            Some(process) => process,
+            None => return Some(text(format!("PID {} has exited", pid)).size(14).into()),
+        };
+
+        if let Some(PendingAction { action, .. }) = self.pending_action {
+            let mut prompt = action.describe(process);
+            prompt[..1].make_ascii_uppercase();
+
+            return Some(
+                row![
+                    text(format!("{}?", prompt)).size(14),
+                    button(text("Confirm").size(14))
+                        .style(theme::Button::Destructive)
+                        .padding(3)
+                        .on_press(Message::ProcessActionConfirmed(pane)),
+                    button(text("Cancel").size(14))
+                        .style(theme::Button::Secondary)
+                        .padding(3)
+                        .on_press(Message::ProcessActionCancelled(pane)),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into(),
+            );
+        }
+
+        let signal_button = |label, signal| {
+            button(text(label).size(14))
+                .style(theme::Button::Secondary)
+                .padding(3)
+                .on_press(Message::ProcessActionRequested(pane, ProcessAction::Signal(signal)))
+        };
+
+        Some(
+            row![
+                text(format!("PID {} ({})", process.pid, process.name)).size(14),
+                signal_button("Terminate", Signal::TERM),
+                signal_button("Kill", Signal::KILL),
+                signal_button("Stop", Signal::STOP),
+                signal_button("Continue", Signal::CONT),
+                pick_list(&Signal::ALL[..], None, move |signal| {
+                    Message::ProcessActionRequested(pane, ProcessAction::Signal(signal))
+                })
+                .placeholder("Signal")
+                .text_size(14),
+                text("Nice").size(14),
+                pick_list((-20..=19).collect::<Vec<i32>>(), Some(process.nice as i32), move |nice| {
+                    Message::ProcessActionRequested(pane, ProcessAction::Renice(nice))
+                })
+                .text_size(14),
+                pick_list(IoPriority::all(), None, move |priority| {
+                    Message::ProcessActionRequested(pane, ProcessAction::SetIoPriority(priority))
+                })
+                .placeholder("I/O priority")
+                .text_size(14),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, ppid: i32, name: &str, cpu_percent: f64, rss: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            name: name.to_string(),
+            cmdline: String::new(),
+            state: 'S',
+            uid: 0,
+            user: String::from("root"),
+            nice: 0,
+            num_threads: 1,
+            rss,
+            start_time: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            start_ticks: 0,
+            cpu_percent,
+            mem_percent: rss as f64 / 100.0,
+        }
+    }
+
+    fn tree_table() -> ProcessTable {
+        ProcessTable {
+            processes: vec![
+                process(1, 0, "init", 1.0, 100),
+                process(10, 1, "sshd", 2.0, 200),
+                process(20, 10, "bash", 4.0, 400),
+                process(30, 20, "vim", 8.0, 800),
+                process(11, 1, "cron", 0.5, 50),
+                // Its parent is not in the list, as when it exited between
+                // the two reads
+                process(99, 500, "orphan", 16.0, 10),
+                process(2, 0, "kthreadd", 0.0, 0),
+                process(3, 2, "kworker", 0.0, 0),
+            ],
+            ..ProcessTable::default()
+        }
+    }
+
+    /// PID, depth, collapsed, CPU% and RSS of each row.
+    fn summary(rows: &[TableRow]) -> Vec<(i32, usize, Option<bool>, f64, u64)> {
+        rows.iter()
+            .map(|row| (row.process.pid, row.depth, row.collapsed, row.usage.cpu_percent, row.usage.rss))
+            .collect()
+    }
+
+    #[test]
+    fn builds_tree_with_subtree_usage() {
+        let table = tree_table();
+        assert_eq!(
+            summary(&table.tree_rows()),
+            [
+                (99, 0, None, 16.0, 10),
+                (1, 0, Some(false), 15.5, 1550),
+                (10, 1, Some(false), 14.0, 1400),
+                (20, 2, Some(false), 12.0, 1200),
+                (30, 3, None, 8.0, 800),
+                (11, 1, None, 0.5, 50),
+                (2, 0, Some(false), 0.0, 0),
+                (3, 1, None, 0.0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_ancestors_of_filtered_processes() {
+        let mut table = tree_table();
+        table.filter.set(String::from("vim"), false);
+        assert_eq!(
+            summary(&table.tree_rows()),
+            [
+                (1, 0, Some(false), 15.5, 1550),
+                (10, 1, Some(false), 14.0, 1400),
+                (20, 2, Some(false), 12.0, 1200),
+                (30, 3, None, 8.0, 800),
+            ]
+        );
+
+        table.filter.set(String::from("nothing"), false);
+        assert!(table.tree_rows().is_empty());
+    }
+
+    #[test]
+    fn hides_collapsed_subtrees() {
+        let mut table = tree_table();
+        table.toggle_subtree(10);
+        table.toggle_subtree(2);
+        assert_eq!(
+            summary(&table.tree_rows()),
+            [
+                (99, 0, None, 16.0, 10),
+                (1, 0, Some(false), 15.5, 1550),
+                (10, 1, Some(true), 14.0, 1400),
+                (11, 1, None, 0.5, 50),
+                (2, 0, Some(true), 0.0, 0),
+            ]
+        );
+
+        table.toggle_subtree(2);
+        let pids: Vec<i32> = table.tree_rows().iter().map(|row| row.process.pid).collect();
+        assert_eq!(pids, [99, 1, 10, 11, 2, 3]);
+    }
+
+    #[test]
+    fn filters_processes() {
+        let with = |pid: i32, name: &str, cmdline: &str, user: &str| ProcessInfo {
+            cmdline: cmdline.to_string(),
+            user: user.to_string(),
+            ..process(pid, 0, name, 0.0, 0)
+        };
+        let processes = [
+            with(1, "systemd", "/sbin/init splash", "root"),
+            with(420, "Xorg", "/usr/lib/xorg/Xorg :0", "root"),
+            with(1234, "firefox", "/usr/lib/firefox/firefox -P work", "alice"),
+            with(77, "kworker/0:1", "", "root"),
+        ];
+
+        let cases: [(&str, bool, &[i32]); 16] = [
+            ("", false, &[1, 420, 1234, 77]),
+            ("", true, &[1, 420, 1234, 77]),
+            ("fire", false, &[1234]),
+            ("FireFox", false, &[1234]),
+            ("splash", false, &[1]),
+            ("alice", false, &[1234]),
+            ("root", false, &[1, 420, 77]),
+            ("42", false, &[420]),
+            // Taken literally without the regex toggle
+            ("^x", false, &[]),
+            ("(", false, &[]),
+            ("^x", true, &[420]),
+            ("fire|init", true, &[1, 1234]),
+            ("work$", true, &[1234]),
+            ("^1$", true, &[1]),
+            (r"^KWORKER/\d", true, &[77]),
+            // Invalid, so nothing is hidden while it is being typed
+            ("(", true, &[1, 420, 1234, 77]),
+        ];
+
+        for (text, use_regex, expected) in cases {
+            let mut filter = ProcessFilter::default();
+            filter.set(text.to_string(), use_regex);
+            let pids: Vec<i32> = processes
+                .iter()
+                .filter(|process| filter.matches(process))
+                .map(|process| process.pid)
+                .collect();
+            assert_eq!(pids, expected, "{:?} regex: {}", text, use_regex);
+        }
+    }
+}
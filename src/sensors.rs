@@ -0,0 +1,240 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use iced::alignment::Alignment;
+use iced::widget::{column, pane_grid, text, Column, Row};
+use iced::{Color, Element, Length};
+use plotters::prelude::ChartBuilder;
+use plotters_iced::plotters_backend::DrawingBackend;
+
+use crate::history::{self, HistoryChart, HistorySeries, TimeWindow};
+use crate::proc::{Sensor, SensorKind};
+use crate::{draw_line_series, draw_mesh, draw_series_labels, ChartSeries, Message};
+
+const CHART_HEIGHT: u16 = 160;
+
+/// Sensor charts per row.
+const CHART_COLUMNS: usize = 3;
+
+/// Readings at or above one of their thresholds
+const ALARM_COLOR: Color = Color::from_rgb(0.86, 0.2, 0.18);
+
+/// Readings of one sensor.
+struct SensorSeries {
+    /// Latest reading, with the label and thresholds
+    sensor: Sensor,
+    history: HistorySeries<f64, 1>,
+}
+
+/// Values of the hwmon sensors over time.
+#[derive(Default)]
+pub struct SensorsChart {
+    sensors: Vec<SensorSeries>,
+    newest_time: Option<DateTime<Utc>>,
+    time_window: TimeWindow,
+}
+
+fn format_value(kind: SensorKind, value: f64) -> String {
+    match kind {
+        SensorKind::Temperature => format!("{:.1}{}", value, kind.unit()),
+        SensorKind::Fan => format!("{:.0} {}", value, kind.unit()),
+        SensorKind::Voltage => format!("{:.3} {}", value, kind.unit()),
+        SensorKind::Power => format!("{:.2} {}", value, kind.unit()),
+    }
+}
+
+/// `Package id 0: 45.0°C (max 80.0°C, crit 100.0°C)`
+fn describe_sensor(sensor: &Sensor, value: f64) -> String {
+    let thresholds: Vec<String> = [("max", sensor.max), ("crit", sensor.crit)]
+        .iter()
+        .filter_map(|(name, threshold)| Some(format!("{} {}", name, format_value(sensor.kind, (*threshold)?))))
+        .collect();
+
+    if thresholds.is_empty() {
+        format!("{}: {}", sensor.label, format_value(sensor.kind, value))
+    } else {
+        format!(
+            "{}: {} ({})",
+            sensor.label,
+            format_value(sensor.kind, value),
+            thresholds.join(", ")
+        )
+    }
+}
+
+impl SensorsChart {
+    pub fn push_data(&mut self, time: DateTime<Utc>, sensors: &[Sensor]) {
+        for sensor in sensors {
+            let series = match self.sensors.iter().position(|s| s.sensor.id == sensor.id) {
+                Some(i) => &mut self.sensors[i],
+                None => {
+                    self.sensors.push(SensorSeries {
+                        sensor: sensor.clone(),
+                        history: HistorySeries::default(),
+                    });
+                    self.sensors.last_mut().unwrap()
+                }
+            };
+            series.sensor = sensor.clone();
+            series.history.push(time, sensor.value);
+        }
+
+        for series in &mut self.sensors {
+            series.history.trim(time);
+        }
+        // Forget sensors whose chip went away
+        self.sensors.retain(|series| !series.history.is_empty());
+        self.newest_time = Some(time);
+    }
+
+    pub fn clear_history(&mut self) {
+        self.sensors.clear();
+        self.newest_time = None;
+    }
+
+    pub fn set_time_window(&mut self, time_window: TimeWindow) {
+        self.time_window = time_window;
+        for series in &self.sensors {
+            series.history.clear_caches();
+        }
+    }
+
+    fn view_sensor<'a>(
+        &self,
+        pane: pane_grid::Pane,
+        series: &'a SensorSeries,
+        range: &Range<DateTime<Utc>>,
+    ) -> Element<'a, Message> {
+        let value = series.history.at(range.end).copied().unwrap_or(series.sensor.value);
+        let alarming = Sensor {
+            value,
+            ..series.sensor.clone()
+        }
+        .is_alarming();
+
+        let mut title = text(describe_sensor(&series.sensor, value)).size(14);
+        if alarming {
+            title = title.style(ALARM_COLOR);
+        }
+
+        let [cache] = series.history.caches();
+        let chart = history::view_chart(
+            SensorChart {
+                series,
+                range: range.clone(),
+            },
+            cache,
+        );
+
+        column![title, chart.map(move |scroll| Message::ChartScrolled(pane, scroll))]
+            .width(Length::Fill)
+            .height(Length::Units(CHART_HEIGHT))
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into()
+    }
+
+    pub fn view(&self, pane: pane_grid::Pane) -> Element<'_, Message> {
+        let newest_time = match self.newest_time {
+            Some(time) => time,
+            None => return text("Loading...").into(),
+        };
+        if self.sensors.is_empty() {
+            return text("No hardware monitoring sensors found").into();
+        }
+        let range = self.time_window.range(newest_time);
+
+        // One section per chip, in the order the chips were found
+        let mut col = Column::new().spacing(20).width(Length::Fill);
+        let mut rest = &self.sensors[..];
+        while let Some(first) = rest.first() {
+            let count = rest
+                .iter()
+                .take_while(|series| series.sensor.id.split('/').next() == first.sensor.id.split('/').next())
+                .count();
+            let (chip, next) = rest.split_at(count);
+            rest = next;
+
+            let charts = chip.chunks(CHART_COLUMNS).fold(Column::new().spacing(10), |charts, chunk| {
+                let row = chunk.iter().fold(Row::new().spacing(15), |row, series| {
+                    row.push(self.view_sensor(pane, series, &range))
+                });
+                // Keep the charts of a short last row as wide as the others
+                let row = (chunk.len()..CHART_COLUMNS).fold(row, |row, _| {
+                    row.push(column![].width(Length::Fill))
+                });
+                charts.push(row)
+            });
+
+            col = col.push(column![text(&first.sensor.chip).size(16), charts].spacing(10));
+        }
+
+        col.into()
+    }
+}
+
+struct SensorChart<'a> {
+    series: &'a SensorSeries,
+    range: Range<DateTime<Utc>>,
+}
+
+impl HistoryChart for SensorChart<'_> {
+    fn build_chart<DB: DrawingBackend>(&self, mut builder: ChartBuilder<DB>) {
+        use plotters::{prelude::*, style::Color};
+
+        const SERIES: [ChartSeries<f64>; 1] = [("value", RGBColor(38, 110, 210), |value| *value)];
+
+        let sensor = &self.series.sensor;
+        let shown = self.series.history.in_range(&self.range).copied();
+        let (min, max) = shown.fold((f64::MAX, f64::MIN), |(min, max), value| (min.min(value), max.max(value)));
+        if min > max {
+            return;
+        }
+
+        // The thresholds are drawn when they are close enough to the
+        // readings to tell something, and fan maximums are left out like
+        // in `Sensor::is_alarming`
+        let max_threshold = if sensor.kind == SensorKind::Fan { None } else { sensor.max };
+        let thresholds: Vec<(&str, f64, RGBColor)> = [
+            ("max", max_threshold, RGBColor(237, 140, 30)),
+            ("crit", sensor.crit, RGBColor(220, 50, 47)),
+        ]
+        .iter()
+        .filter_map(|(name, threshold, color)| Some((*name, (*threshold)?, *color)))
+        .filter(|(_, threshold, _)| *threshold > 0.0 && *threshold <= max * 1.5)
+        .collect();
+
+        let top = thresholds.iter().map(|(_, threshold, _)| *threshold).fold(max, f64::max);
+        // Readings that barely move still get a visible range
+        let margin = ((top - min) * 0.1).max(top.abs() * 0.05).max(0.1);
+        // Readings that never go negative do not get a negative axis, but
+        // ones that do, such as sub-zero temperatures, are kept in view
+        let bottom = if min >= 0.0 { (min - margin).max(0.0) } else { min - margin };
+        let y_range = bottom..top + margin;
+
+        let mut chart = builder
+            .x_label_area_size(0)
+            .y_label_area_size(60)
+            .margin(10)
+            .build_cartesian_2d(self.range.clone(), y_range)
+            .expect("failed to build chart");
+
+        let kind = sensor.kind;
+        draw_mesh(&mut chart, 4, &|y| format_value(kind, *y));
+
+        for (name, threshold, color) in thresholds {
+            let style = ShapeStyle::from(color.mix(0.7)).stroke_width(1);
+            chart
+                .draw_series(LineSeries::new(
+                    [(self.range.start, threshold), (self.range.end, threshold)],
+                    style,
+                ))
+                .expect("failed to draw chart data")
+                .label(name)
+                .legend(move |(x, y)| PathElement::new([(x, y), (x + 8, y)], style));
+        }
+
+        draw_line_series(&mut chart, self.series.history.iter(), &self.range, &SERIES);
+        draw_series_labels(&mut chart);
+    }
+}
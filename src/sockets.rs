@@ -0,0 +1,142 @@
+use iced::alignment::Alignment;
+use iced::widget::{checkbox, column, pane_grid, row, text, text_input, Column, Row};
+use iced::{Element, Length};
+
+use crate::proc::SocketInfo;
+use crate::Message;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SocketColumn {
+    Protocol,
+    Local,
+    Remote,
+    State,
+    Pid,
+    Command,
+}
+
+impl SocketColumn {
+    const ALL: [SocketColumn; 6] = [
+        SocketColumn::Protocol,
+        SocketColumn::Local,
+        SocketColumn::Remote,
+        SocketColumn::State,
+        SocketColumn::Pid,
+        SocketColumn::Command,
+    ];
+
+    fn width(&self) -> Length {
+        match self {
+            SocketColumn::Protocol => Length::Units(45),
+            SocketColumn::Local => Length::FillPortion(3),
+            SocketColumn::Remote => Length::FillPortion(3),
+            SocketColumn::State => Length::Units(90),
+            SocketColumn::Pid => Length::Units(70),
+            SocketColumn::Command => Length::FillPortion(2),
+        }
+    }
+
+    fn cell(&self, socket: &SocketInfo) -> String {
+        match self {
+            SocketColumn::Protocol => socket.protocol.to_string(),
+            SocketColumn::Local => socket.local.clone(),
+            SocketColumn::Remote => socket.remote.clone(),
+            SocketColumn::State => socket.state.to_string(),
+            SocketColumn::Pid => socket.owner.as_ref().map(|(pid, _)| pid.to_string()).unwrap_or_default(),
+            SocketColumn::Command => socket.owner.as_ref().map(|(_, command)| command.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for SocketColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SocketColumn::Protocol => write!(f, "Proto"),
+            SocketColumn::Local => write!(f, "Local address"),
+            SocketColumn::Remote => write!(f, "Remote address"),
+            SocketColumn::State => write!(f, "State"),
+            SocketColumn::Pid => write!(f, "PID"),
+            SocketColumn::Command => write!(f, "Command"),
+        }
+    }
+}
+
+/// Filters of a sockets pane. The sockets themselves come from the
+/// snapshot shown by the pane.
+#[derive(Debug, Default)]
+pub struct SocketFilter {
+    pub listening_only: bool,
+    /// Text of the port field, which may not be a valid port
+    pub port: String,
+}
+
+impl SocketFilter {
+    fn port(&self) -> Option<u16> {
+        self.port.trim().parse().ok()
+    }
+
+    fn matches(&self, socket: &SocketInfo) -> bool {
+        if self.listening_only && !socket.is_listening() {
+            return false;
+        }
+        match self.port() {
+            // Port 0 is what unconnected sockets report for the remote end
+            Some(port) => [socket.local_port, socket.remote_port]
+                .iter()
+                .any(|p| *p == Some(port) && port != 0),
+            None => true,
+        }
+    }
+}
+
+fn view_row(socket: &SocketInfo) -> Row<'_, Message> {
+    SocketColumn::ALL.iter().fold(row![].spacing(5), |cells, column| {
+        cells.push(text(column.cell(socket)).size(14).width(column.width()))
+    })
+}
+
+pub fn view<'a>(
+    pane: pane_grid::Pane,
+    sockets: Option<&'a Vec<SocketInfo>>,
+    filter: &SocketFilter,
+) -> Element<'a, Message> {
+    let sockets = match sockets {
+        Some(sockets) => sockets,
+        None => return text("Loading...").into(),
+    };
+
+    let shown: Vec<&SocketInfo> = sockets.iter().filter(|socket| filter.matches(socket)).collect();
+
+    let status = if !filter.port.trim().is_empty() && filter.port().is_none() {
+        format!("Invalid port: {}", filter.port.trim())
+    } else {
+        format!("{} of {} sockets", shown.len(), sockets.len())
+    };
+
+    let controls = row![
+        checkbox("Listening only", filter.listening_only, move |listening_only| {
+            Message::SocketsListeningToggled(pane, listening_only)
+        })
+        .size(14)
+        .text_size(14),
+        text_input("Port", &filter.port, move |port| Message::SocketsPortChanged(pane, port))
+            .padding(5)
+            .size(14)
+            .width(Length::Units(100)),
+        text(status).size(14),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let header = SocketColumn::ALL.iter().fold(row![].spacing(5), |header, column| {
+        header.push(text(column.to_string()).size(14).width(column.width()))
+    });
+
+    shown
+        .into_iter()
+        .fold(column![controls, header].spacing(2), |col: Column<'a, Message>, socket| {
+            col.push(view_row(socket))
+        })
+        .width(Length::Fill)
+        .into()
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use chrono::Local;
+use iced::alignment::Alignment;
+use iced::theme;
+use iced::widget::{button, column, row, text, Column};
+use iced::{Element, Length};
+
+use crate::process_table::format_rss;
+use crate::proc::HostInfo;
+use crate::Message;
+
+const KEY_WIDTH: Length = Length::Units(160);
+
+/// `3 days, 04:05:06`, like uptime.
+fn format_uptime(uptime: Duration) -> String {
+    let seconds = uptime.as_secs();
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    let clock = format!("{:02}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    match days {
+        0 => clock,
+        1 => format!("1 day, {}", clock),
+        _ => format!("{} days, {}", days, clock),
+    }
+}
+
+/// The rows of the pane, in order. Fields that could not be read are shown
+/// as unknown rather than left out, so the layout stays the same.
+fn rows(host: &HostInfo) -> Vec<(&'static str, String)> {
+    let unknown = || String::from("Unknown");
+    let uname = host.uname.as_ref();
+    let cpu = host.cpu.as_ref();
+
+    let mut rows = vec![
+        ("Hostname", uname.map(|u| u.nodename.clone()).unwrap_or_else(unknown)),
+        ("Operating system", host.os.clone().unwrap_or_else(unknown)),
+        (
+            "Kernel",
+            uname.map(|u| format!("{} {}", u.sysname, u.release)).unwrap_or_else(unknown),
+        ),
+        ("Kernel build", uname.map(|u| u.version.clone()).unwrap_or_else(unknown)),
+        ("Architecture", uname.map(|u| u.machine.clone()).unwrap_or_else(unknown)),
+        ("Uptime", host.uptime.map(format_uptime).unwrap_or_else(unknown)),
+        (
+            "Boot time",
+            host.boot_time
+                .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(unknown),
+        ),
+        ("CPU model", cpu.and_then(|cpu| cpu.model.clone()).unwrap_or_else(unknown)),
+        (
+            "CPU topology",
+            cpu.map(|cpu| {
+                format!(
+                    "{} socket(s), {} core(s), {} thread(s)",
+                    cpu.sockets, cpu.cores, cpu.threads
+                )
+            })
+            .unwrap_or_else(unknown),
+        ),
+    ];
+
+    if let Some(cpu) = cpu {
+        rows.extend(cpu.caches.iter().map(|cache| ("CPU cache", cache.to_string())));
+    }
+
+    rows.extend([
+        ("Total memory", host.mem_total.map(format_rss).unwrap_or_else(unknown)),
+        (
+            "Virtualization",
+            host.virtualization.clone().unwrap_or_else(|| String::from("None detected")),
+        ),
+        (
+            "Container",
+            host.container.clone().unwrap_or_else(|| String::from("None detected")),
+        ),
+    ]);
+
+    if host.users.is_empty() {
+        rows.push(("Logged in users", String::from("None")));
+    }
+    rows.extend(host.users.iter().map(|user| {
+        let session = if user.host.is_empty() {
+            format!("{} on {}", user.user, user.line)
+        } else {
+            format!("{} on {} from {}", user.user, user.line, user.host)
+        };
+        ("Logged in user", session)
+    }));
+
+    rows
+}
+
+/// The rows as `key: value` lines, for the clipboard.
+fn to_text(rows: &[(&'static str, String)]) -> String {
+    rows.iter()
+        .map(|(key, value)| format!("{}: {}\n", key, value))
+        .collect()
+}
+
+pub fn view(host: Option<&HostInfo>) -> Element<'_, Message> {
+    let host = match host {
+        Some(host) => host,
+        None => return text("Loading...").into(),
+    };
+
+    let rows = rows(host);
+
+    let header = row![
+        text("System information").size(18).width(Length::Fill),
+        button(text("Copy").size(14))
+            .style(theme::Button::Secondary)
+            .padding(3)
+            .on_press(Message::CopyToClipboard(to_text(&rows))),
+    ]
+    .align_items(Alignment::Center);
+
+    rows.into_iter()
+        .fold(column![header].spacing(5), |col: Column<'_, Message>, (key, value)| {
+            col.push(
+                row![
+                    text(key).size(14).width(KEY_WIDTH),
+                    text(value).size(14).width(Length::Fill),
+                ]
+                .spacing(5),
+            )
+        })
+        .width(Length::Fill)
+        .into()
+}